@@ -0,0 +1,133 @@
+//! Checking the resolved dependencies against a list of approved versions
+//!
+//! The allowlist is a TOML file mapping each package name to the requirement, or array of
+//! requirements, its version must match, e.g. `serde = "^1.0"` or `libc = ["0.2.30", "0.2.32"]`.
+//! Unlike in a manifest a bare version only approves that exact version, so ranges need an
+//! operator.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use toml::Value;
+
+use cargo_files::Lockfile;
+use error::{CliError, CliResult};
+use version;
+
+/// The packages in `lockfile`, with their versions, which aren't approved by the allowlist
+///
+/// Only packages with a source are checked, so path dependencies and workspace members, which
+/// can't be on an allowlist of published versions, are skipped.
+pub fn unapproved(allowlist: &Path, lockfile: &Path) -> CliResult<Vec<(String, String)>> {
+    let mut contents = String::new();
+    File::open(allowlist)?.read_to_string(&mut contents)?;
+    let approved = match contents.parse::<Value>() {
+        Ok(Value::Table(table)) => table,
+        Ok(_) => unreachable!(),
+        Err(e) => {
+            return Err(CliError::Generic(format!(
+                "Cannot parse allowlist {}: {}",
+                allowlist.display(),
+                e
+            )))
+        }
+    };
+
+    let lockfile = Lockfile::from_lockfile_path(lockfile)?;
+    let mut unapproved = vec![];
+    for package in lockfile.package.unwrap_or_default() {
+        if package.source.is_none() {
+            continue;
+        }
+        let allowed = match approved.get(&package.name) {
+            Some(Value::String(req)) => approves(req, &package.version),
+            Some(Value::Array(reqs)) => reqs.iter().any(|req| match *req {
+                Value::String(ref req) => approves(req, &package.version),
+                _ => false,
+            }),
+            _ => false,
+        };
+        if !allowed {
+            unapproved.push((package.name, package.version));
+        }
+    }
+    unapproved.sort();
+    unapproved.dedup();
+    Ok(unapproved)
+}
+
+/// Whether the allowlist entry `requirement` approves `version`, reading bare versions such as
+/// `1.0.0` as `=1.0.0`
+fn approves(requirement: &str, version: &str) -> bool {
+    let requirement: Vec<_> = requirement
+        .split(',')
+        .map(|comparator| {
+            let comparator = comparator.trim();
+            if comparator.starts_with(|c: char| c.is_ascii_digit()) {
+                format!("={}", comparator)
+            } else {
+                comparator.to_owned()
+            }
+        })
+        .collect();
+    version::matches(&requirement.join(", "), version)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Write;
+
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn bare_versions_are_exact() {
+        assert!(approves("1.0.0", "1.0.0"));
+        assert!(!approves("1.0.0", "1.0.9"));
+        assert!(approves("^1.0.0", "1.0.9"));
+        assert!(approves(">=1.0, <1.2", "1.1.0"));
+        assert!(!approves("1.0.0, <1.2", "1.1.0"));
+    }
+
+    #[test]
+    fn unapproved_packages() {
+        let dir = TempDir::new("allowlist").unwrap();
+        let allowlist = dir.path().join("allowlist.toml");
+        let lockfile = dir.path().join("Cargo.lock");
+        let registry = "registry+https://github.com/rust-lang/crates.io-index";
+        File::create(&allowlist)
+            .unwrap()
+            .write_all(b"a = \"1.0.0\"\nb = [\"0.2.30\", \"^0.3\"]\nc = \"^2\"\n")
+            .unwrap();
+        let mut contents = String::from("[root]\nname = \"p\"\nversion = \"0.1.0\"\n");
+        for &(name, version, source) in &[
+            ("a", "1.0.9", Some(registry)),
+            ("b", "0.3.1", Some(registry)),
+            ("c", "1.5.0", Some(registry)),
+            ("d", "1.0.0", Some(registry)),
+            ("local", "0.1.0", None),
+        ] {
+            contents.push_str(&format!(
+                "\n[[package]]\nname = \"{}\"\nversion = \"{}\"\n",
+                name,
+                version
+            ));
+            if let Some(source) = source {
+                contents.push_str(&format!("source = \"{}\"\n", source));
+            }
+        }
+        File::create(&lockfile)
+            .unwrap()
+            .write_all(contents.as_bytes())
+            .unwrap();
+
+        let expected: Vec<_> = [("a", "1.0.9"), ("c", "1.5.0"), ("d", "1.0.0")]
+            .iter()
+            .map(|&(n, v)| (n.to_owned(), v.to_owned()))
+            .collect();
+        assert_eq!(unapproved(&allowlist, &lockfile).unwrap(), expected);
+    }
+}
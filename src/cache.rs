@@ -0,0 +1,252 @@
+//! Caches the results of a run so that repeated runs with unchanged inputs return instantly
+//!
+//! Results are stored under the `target` directory next to the manifest, keyed by a hash of the
+//! manifests, the lockfile and the options which affect which dependencies are reported, along
+//! with the `HEAD` commit for `--changed-only`.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use cargo_files::{DependencyKind, DependencyStatus, Update, WorkspaceManifest};
+use config::Config;
+use error::CliResult;
+use git;
+
+/// Hashes every input which could change the results of a run
+pub fn key(cfg: &Config) -> CliResult<u64> {
+    let mut hasher = DefaultHasher::new();
+    hash_file(&cfg.manifest, &mut hasher)?;
+    hash_file(&cfg.lockfile, &mut hasher)?;
+    let ws_manifest = WorkspaceManifest::from_manifest_path(&cfg.manifest)?;
+    for member in ws_manifest.members(&cfg.manifest) {
+        let member_manifest = WorkspaceManifest::member_manifest_path(&cfg.manifest, &member);
+        hash_file(&member_manifest, &mut hasher)?;
+    }
+    cfg.to_update.hash(&mut hasher);
+    cfg.root.hash(&mut hasher);
+    cfg.depth.hash(&mut hasher);
+    cfg.workspace.hash(&mut hasher);
+    cfg.all_members.hash(&mut hasher);
+    cfg.exclude_members.hash(&mut hasher);
+    cfg.registry_index.hash(&mut hasher);
+    cfg.changed_only.hash(&mut hasher);
+    // The dependencies are compared with the manifest at `HEAD`, which moves with each commit
+    if cfg.changed_only {
+        git::head_commit(&cfg.manifest)?.hash(&mut hasher);
+    }
+    cfg.only_explicit.hash(&mut hasher);
+    cfg.compatible_latest.hash(&mut hasher);
+    cfg.assume_version.hash(&mut hasher);
+    cfg.cargo.hash(&mut hasher);
+    cfg.target.as_ref().map(|t| t.triple()).hash(&mut hasher);
+    Ok(hasher.finish())
+}
+
+/// Returns the cached results for `key`, if there are any
+pub fn load(cfg: &Config, key: u64) -> Option<Vec<DependencyStatus>> {
+    read_statuses(&cache_path(cfg, key))
+}
+
+/// Stores the results for `key`, replacing any results cached for previous inputs
+pub fn store(cfg: &Config, key: u64, statuses: &[DependencyStatus]) -> CliResult<()> {
+    let path = cache_path(cfg, key);
+    let dir = path.parent().unwrap();
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    fs::create_dir_all(dir)?;
+    write_statuses(&path, statuses)
+}
+
+/// Returns the results of `member` saved by an earlier `--resume` run with the same `key`
+pub fn load_member(cfg: &Config, key: u64, member: &str) -> Option<Vec<DependencyStatus>> {
+    read_statuses(&member_path(cfg, key, member))
+}
+
+/// Prepares to save member results for `key`, removing any saved for other inputs since they
+/// can't be resumed any more
+pub fn start_members(cfg: &Config, key: u64) -> CliResult<()> {
+    let dir = member_dir(cfg, key);
+    if let Ok(entries) = fs::read_dir(dir.parent().unwrap()) {
+        for entry in entries {
+            let entry = entry?.path();
+            if entry != dir {
+                fs::remove_dir_all(entry)?;
+            }
+        }
+    }
+    fs::create_dir_all(dir)?;
+    Ok(())
+}
+
+/// Saves the results of `member`, so a `--resume` run after an interruption can skip it
+pub fn store_member(
+    cfg: &Config,
+    key: u64,
+    member: &str,
+    statuses: &[DependencyStatus],
+) -> CliResult<()> {
+    write_statuses(&member_path(cfg, key, member), statuses)
+}
+
+/// Removes the results saved for `--resume` once every member has been checked
+pub fn clear_members(cfg: &Config, key: u64) -> CliResult<()> {
+    let dir = member_dir(cfg, key);
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+fn read_statuses(path: &Path) -> Option<Vec<DependencyStatus>> {
+    let mut contents = String::new();
+    if File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut contents))
+        .is_err()
+    {
+        return None;
+    }
+    let mut statuses = vec![];
+    for entry in contents.lines() {
+        let fields: Vec<_> = entry.split('\t').collect();
+        if fields.len() != 10 {
+            return None;
+        }
+        let kind = match fields[0] {
+            "normal" => DependencyKind::Normal,
+            "dev" => DependencyKind::Development,
+            "build" => DependencyKind::Build,
+            _ => return None,
+        };
+        let optional = |f: &str| if f.is_empty() {
+            None
+        } else {
+            Some(f.to_owned())
+        };
+        let update = |f: &str| match f {
+            "=" => Update::Unchanged,
+            "-" => Update::Removed,
+            "!" => Update::Patched,
+            v => match v.find(' ') {
+                // Versions never contain a space, so it separates the version from a new source
+                Some(i) => Update::SourceChanged(v[..i].to_owned(), v[i + 1..].to_owned()),
+                None => Update::Version(v.to_owned()),
+            },
+        };
+        statuses.push(DependencyStatus {
+            kind,
+            parent: optional(fields[1]),
+            name: fields[2].to_owned(),
+            requirement: optional(fields[3]),
+            project: fields[4].to_owned(),
+            checksum: optional(fields[5]),
+            source: optional(fields[6]),
+            compat: update(fields[7]),
+            latest: update(fields[8]),
+            path: fields[9].split(' ').map(|p| p.to_owned()).collect(),
+        });
+    }
+    Some(statuses)
+}
+
+fn write_statuses(path: &Path, statuses: &[DependencyStatus]) -> CliResult<()> {
+    let mut file = File::create(path)?;
+    let update = |u: &Update| match *u {
+        Update::Unchanged => "=".to_owned(),
+        Update::Removed => "-".to_owned(),
+        Update::Patched => "!".to_owned(),
+        Update::Version(ref v) => v.clone(),
+        Update::SourceChanged(ref v, ref source) => format!("{} {}", v, source),
+    };
+    for status in statuses {
+        writeln!(
+            file,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            status.kind.as_str(),
+            status.parent.as_deref().unwrap_or(""),
+            status.name,
+            status.requirement.as_deref().unwrap_or(""),
+            status.project,
+            status.checksum.as_deref().unwrap_or(""),
+            status.source.as_deref().unwrap_or(""),
+            update(&status.compat),
+            update(&status.latest),
+            status.path.join(" ")
+        )?;
+    }
+    Ok(())
+}
+
+fn cache_path(cfg: &Config, key: u64) -> PathBuf {
+    cfg.manifest
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("target")
+        .join("cargo-outdated")
+        .join(format!("{:016x}", key))
+}
+
+fn member_dir(cfg: &Config, key: u64) -> PathBuf {
+    cfg.manifest
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("target")
+        .join("cargo-outdated-resume")
+        .join(format!("{:016x}", key))
+}
+
+/// Members are named by a hash of their path, which may contain separators
+fn member_path(cfg: &Config, key: u64, member: &str) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    member.hash(&mut hasher);
+    member_dir(cfg, key).join(format!("{:016x}", hasher.finish()))
+}
+
+fn hash_file<H: Hasher>(path: &Path, hasher: &mut H) -> CliResult<()> {
+    let mut contents = vec![];
+    File::open(path)?.read_to_end(&mut contents)?;
+    contents.hash(hasher);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use fixtures::{self, Project};
+
+    use super::*;
+
+    fn git(project: &Project, args: &[&str]) {
+        let status = process::Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(project.path(""))
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success());
+    }
+
+    #[test]
+    fn changed_only_key_follows_the_head_commit() {
+        let project = Project::new();
+        project.file("Cargo.toml", "[package]\nname = \"p\"\nversion = \"0.1.0\"\n");
+        project.file("Cargo.lock", "");
+        git(&project, &["init", "-q"]);
+        git(&project, &["add", "Cargo.toml"]);
+        git(&project, &["commit", "-q", "-m", "first"]);
+        let changed_only = fixtures::matches(&project.args(&["--changed-only"]));
+        let changed_only = Config::from_matches(&changed_only).unwrap();
+        let all = fixtures::matches(&project.args(&[]));
+        let all = Config::from_matches(&all).unwrap();
+
+        let before = (key(&changed_only).unwrap(), key(&all).unwrap());
+        git(&project, &["commit", "-q", "--allow-empty", "-m", "second"]);
+        assert_ne!(key(&changed_only).unwrap(), before.0);
+        assert_eq!(key(&all).unwrap(), before.1);
+    }
+}
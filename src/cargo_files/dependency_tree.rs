@@ -1,8 +1,10 @@
 use std::cell::RefCell;
 use std::rc::{Rc, Weak};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use config::Config;
+use version::{self, Severity};
 use super::lockfile::Lockfile;
+use super::manifest::DependencyKind;
 
 type PackageCell = RefCell<Package>;
 
@@ -10,6 +12,10 @@ type PackageCell = RefCell<Package>;
 pub struct Package {
     pub name: String,
     pub version: String,
+    /// The lockfile checksum, only recorded for registry packages
+    pub checksum: Option<String>,
+    /// The lockfile source, `None` for path dependencies
+    pub source: Option<String>,
     pub dependencies: Option<HashMap<String, Weak<PackageCell>>>,
 }
 
@@ -18,11 +24,78 @@ impl Package {
         Package {
             name: name.to_owned(),
             version: version.to_owned(),
+            checksum: None,
+            source: None,
             dependencies: None,
         }
     }
 }
 
+/// The version a dependency has in the compat or latest graph, relative to the current graph
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Update {
+    Unchanged,
+    Version(String),
+    /// Resolved from another source, e.g. a `[patch]` pointing at a git repository, along with
+    /// the version and the new source (`path` for path dependencies)
+    SourceChanged(String, String),
+    Removed,
+    /// Overridden by a `[patch]`, so the registry's latest version doesn't apply to it
+    Patched,
+}
+
+/// A dependency which has a newer version in the compat or latest graph
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DependencyStatus {
+    pub kind: DependencyKind,
+    /// The package depending on this one, or `None` for direct dependencies
+    pub parent: Option<String>,
+    pub name: String,
+    /// The requirement from the manifest, only known for direct dependencies
+    pub requirement: Option<String>,
+    pub project: String,
+    /// The lockfile checksum of the project version
+    pub checksum: Option<String>,
+    /// The lockfile source of the project version
+    pub source: Option<String>,
+    pub compat: Update,
+    pub latest: Update,
+    /// The shortest chain of packages from the root to this dependency, inclusive
+    pub path: Vec<String>,
+}
+
+impl DependencyStatus {
+    /// The name shown in reports, including the parent of transitive dependencies
+    pub fn display_name(&self) -> String {
+        match self.parent {
+            Some(ref parent) => format!("{}->{}", parent, self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    /// The requirement which would allow the latest version, when the one in the manifest
+    /// doesn't, e.g. `^2` for a requirement of `^1` and a latest version of `2.1.0`
+    pub fn suggested_requirement(&self) -> Option<String> {
+        match (&self.requirement, &self.latest) {
+            (Some(req), Update::Version(v)) if !version::matches(req, v) => {
+                Some(version::compatible_requirement(v))
+            }
+            _ => None,
+        }
+    }
+
+    /// The severity of the newest update available, preferring the latest version over the
+    /// SemVer compatible one
+    pub fn severity(&self) -> Option<Severity> {
+        match (&self.latest, &self.compat) {
+            (&Update::Version(ref v), _) | (_, &Update::Version(ref v)) => {
+                Severity::between(&self.project, v)
+            }
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct DependencyTree {
     pub root: Weak<PackageCell>,
@@ -44,7 +117,7 @@ impl DependencyTree {
             Some(r) => if r == lockfile.root.name {
                 format!("{} {}", r, lockfile.root.version)
             } else {
-                Self::find_root(r, &lockfile.root.dependencies)
+                Self::find_root(r, lockfile)
             },
             None => lockfile.root.name.clone() + " " + &lockfile.root.version,
         };
@@ -57,37 +130,55 @@ impl DependencyTree {
         }
     }
 
-    pub fn print_list_to_vec(
+    /// The dependencies of `tree_curr` with a different version in the compat or latest graph
+    ///
+    /// Nothing is printed, see the `report` module for rendering the result.
+    pub fn compare_versions(
         tree_curr: &DependencyTree,
         tree_comp: &DependencyTree,
         tree_latest: &DependencyTree,
+        requirements: &HashMap<String, String>,
+        kinds: &HashMap<String, DependencyKind>,
+        patched: &HashSet<String>,
         cfg: &Config,
-    ) -> Vec<String> {
-        let mut lines = vec![];
+    ) -> Vec<DependencyStatus> {
+        let mut statuses = vec![];
         let root_curr = tree_curr.root.upgrade().unwrap();
         let root_comp = tree_comp.root.upgrade().unwrap();
         let root_latest = tree_latest.root.upgrade().unwrap();
-        Self::print_list_to_vec_recursive(
+        Self::compare_versions_recursive(
             root_curr,
             Some(root_comp),
             Some(root_latest),
             "",
-            &mut lines,
+            &mut statuses,
             true,
+            DependencyKind::Normal,
+            requirements,
+            kinds,
+            patched,
             cfg,
         );
-        lines.sort();
-        lines.dedup();
-        lines
+        for status in &mut statuses {
+            status.path = tree_curr.path_to(&status.name);
+        }
+        statuses.sort();
+        statuses.dedup();
+        statuses
     }
 
-    fn print_list_to_vec_recursive(
+    #[allow(clippy::too_many_arguments)]
+    fn compare_versions_recursive(
         curr: Rc<PackageCell>,
         comp: Option<Rc<PackageCell>>,
         latest: Option<Rc<PackageCell>>,
         parent: &str,
-        lines: &mut Vec<String>,
+        statuses: &mut Vec<DependencyStatus>,
         curr_is_root: bool,
+        kind: DependencyKind,
+        requirements: &HashMap<String, String>,
+        kinds: &HashMap<String, DependencyKind>,
+        patched: &HashSet<String>,
         cfg: &Config,
     ) {
         if cfg.to_update.is_none() ||
@@ -96,31 +187,62 @@ impl DependencyTree {
                 .unwrap()
                 .contains(&curr.borrow().name.as_str())
         {
-            let name = if !(curr_is_root || parent.is_empty()) {
-                format!("{}->{}", parent, curr.borrow().name)
-            } else {
-                curr.borrow().name.clone()
-            };
-            let updated_version = |updated: &Option<Rc<PackageCell>>| -> Option<String> {
+            // The commit of a git source changes with the version, only the repository matters
+            fn source_id(pac: &Package) -> &str {
+                match pac.source {
+                    Some(ref source) => source.split('#').next().unwrap_or(source),
+                    None => "path",
+                }
+            }
+            let updated_version = |updated: &Option<Rc<PackageCell>>| -> Update {
                 match *updated {
+                    Some(ref pac) if source_id(&curr.borrow()) != source_id(&pac.borrow()) => {
+                        Update::SourceChanged(
+                            pac.borrow().version.clone(),
+                            source_id(&pac.borrow()).to_owned(),
+                        )
+                    }
                     Some(ref pac) if curr.borrow().version != pac.borrow().version => {
-                        Some(pac.borrow().version.clone())
+                        Update::Version(pac.borrow().version.clone())
                     }
-                    Some(_) => None,
-                    None => Some("  RM  ".to_owned()),
+                    Some(_) => Update::Unchanged,
+                    None => Update::Removed,
                 }
             };
             let comp_ver = updated_version(&comp);
-            let latest_ver = updated_version(&latest);
-
-            if comp_ver.is_some() || latest_ver.is_some() {
-                lines.push(format!(
-                    "{}\t   {}\t   {}\t  {}\n",
-                    name,
-                    curr.borrow().version,
-                    comp_ver.unwrap_or_else(|| "  --  ".to_owned()),
-                    latest_ver.unwrap_or_else(|| "  --  ".to_owned())
-                ));
+            // A patched package usually points at a fork, which the registry knows nothing about
+            let is_patched = patched.contains(&curr.borrow().name);
+            let latest_ver = if is_patched {
+                Update::Patched
+            } else {
+                updated_version(&latest)
+            };
+            // Only direct dependencies have a requirement in the manifest
+            let requirement = if !curr_is_root && parent.is_empty() {
+                requirements.get(&curr.borrow().name).cloned()
+            } else {
+                None
+            };
+
+            let latest_changed = latest_ver != Update::Unchanged && latest_ver != Update::Patched;
+            // The root only changes version with --assume-version, which isn't an update
+            if !curr_is_root && (comp_ver != Update::Unchanged || latest_changed) {
+                statuses.push(DependencyStatus {
+                    kind,
+                    parent: if curr_is_root || parent.is_empty() {
+                        None
+                    } else {
+                        Some(parent.to_owned())
+                    },
+                    name: curr.borrow().name.clone(),
+                    requirement,
+                    project: curr.borrow().version.clone(),
+                    checksum: curr.borrow().checksum.clone(),
+                    source: curr.borrow().source.clone(),
+                    compat: comp_ver,
+                    latest: latest_ver,
+                    path: vec![],
+                });
             }
         }
 
@@ -144,7 +266,16 @@ impl DependencyTree {
                 let next_curr = next_curr.upgrade().unwrap();
                 let next_comp = next_node(&next_curr.borrow().name, &comp);
                 let next_latest = next_node(&next_curr.borrow().name, &latest);
-                Self::print_list_to_vec_recursive(
+                // Transitive dependencies take the kind of the direct dependency they came from
+                let next_kind = if curr_is_root {
+                    kinds
+                        .get(&next_curr.borrow().name)
+                        .cloned()
+                        .unwrap_or(DependencyKind::Normal)
+                } else {
+                    kind
+                };
+                Self::compare_versions_recursive(
                     next_curr,
                     next_comp,
                     next_latest,
@@ -153,16 +284,133 @@ impl DependencyTree {
                     } else {
                         curr.borrow().name.clone()
                     },
-                    lines,
+                    statuses,
                     false,
+                    next_kind,
+                    requirements,
+                    kinds,
+                    patched,
                     cfg,
                 );
             }
         }
     }
 
-    fn find_root(root: &str, dependencies: &Option<Vec<String>>) -> String {
-        if let Some(ref deps) = *dependencies {
+    /// Packages with a single version in `tree_curr` which have more than one in `tree_latest`,
+    /// along with their versions in `tree_latest`
+    pub fn new_duplicates(
+        tree_curr: &DependencyTree,
+        tree_latest: &DependencyTree,
+    ) -> Vec<(String, Vec<String>)> {
+        let curr = tree_curr.versions();
+        let mut duplicates: Vec<_> = tree_latest
+            .versions()
+            .into_iter()
+            .filter(|(name, versions)| {
+                versions.len() > 1 && curr.get(name).map(|v| v.len()) == Some(1)
+            })
+            .map(|(name, versions)| (name, versions.into_iter().collect()))
+            .collect();
+        duplicates.sort();
+        duplicates
+    }
+
+    /// The number of packages in `tree_latest` which aren't in `tree_curr`, and the other way
+    /// around, comparing versions as well as names
+    pub fn package_delta(
+        tree_curr: &DependencyTree,
+        tree_latest: &DependencyTree,
+    ) -> (usize, usize) {
+        let curr = tree_curr.package_ids();
+        let latest = tree_latest.package_ids();
+        (latest.difference(&curr).count(), curr.difference(&latest).count())
+    }
+
+    /// The versions of each package in the graph
+    fn versions(&self) -> HashMap<String, BTreeSet<String>> {
+        let mut versions = HashMap::new();
+        for pac in self.packages.values() {
+            let pac = pac.borrow();
+            versions
+                .entry(pac.name.clone())
+                .or_insert_with(BTreeSet::new)
+                .insert(pac.version.clone());
+        }
+        versions
+    }
+
+    /// The graph in Graphviz DOT format, with a node per package version
+    pub fn to_dot(&self) -> String {
+        let id = |pac: &Package| format!("\"{} {}\"", pac.name, pac.version);
+        let mut lines = vec![];
+        for pac in self.packages.values() {
+            let pac = pac.borrow();
+            lines.push(format!("    {};", id(&pac)));
+            if let Some(ref deps) = pac.dependencies {
+                for dep in deps.values() {
+                    let dep = dep.upgrade().unwrap();
+                    lines.push(format!("    {} -> {};", id(&pac), id(&dep.borrow())));
+                }
+            }
+        }
+        // Sorted so the same lockfile always gives the same file
+        lines.sort();
+        format!("digraph dependencies {{\n{}\n}}\n", lines.join("\n"))
+    }
+
+    /// The `name version` of every package in the graph other than the root
+    pub fn package_ids(&self) -> HashSet<String> {
+        let root = self.root.upgrade().unwrap();
+        let root = root.borrow();
+        let root_id = format!("{} {}", root.name, root.version);
+        self.packages
+            .keys()
+            .filter(|id| **id != root_id)
+            .cloned()
+            .collect()
+    }
+
+    /// The names of the packages the root depends on directly
+    pub fn direct_dependencies(&self) -> HashSet<String> {
+        let root = self.root.upgrade().unwrap();
+        let root = root.borrow();
+        match root.dependencies {
+            Some(ref deps) => deps.keys().cloned().collect(),
+            None => HashSet::new(),
+        }
+    }
+
+    /// The shortest chain of package names from the root to the first package called `name`
+    ///
+    /// Ties are broken alphabetically so the same chain is reported on every run.
+    pub fn path_to(&self, name: &str) -> Vec<String> {
+        let mut queue = VecDeque::new();
+        let mut seen = HashSet::new();
+        queue.push_back(vec![self.root.upgrade().unwrap()]);
+        while let Some(path) = queue.pop_front() {
+            let last = path.last().unwrap().clone();
+            let last = last.borrow();
+            if last.name == name {
+                return path.iter().map(|p| p.borrow().name.clone()).collect();
+            }
+            if let Some(ref deps) = last.dependencies {
+                let mut next: Vec<_> = deps.values().map(|d| d.upgrade().unwrap()).collect();
+                next.sort_by(|a, b| a.borrow().name.cmp(&b.borrow().name));
+                for pac in next {
+                    let id = format!("{} {}", pac.borrow().name, pac.borrow().version);
+                    if seen.insert(id) {
+                        let mut next_path = path.clone();
+                        next_path.push(pac);
+                        queue.push_back(next_path);
+                    }
+                }
+            }
+        }
+        vec![]
+    }
+
+    fn find_root(root: &str, lockfile: &Lockfile) -> String {
+        if let Some(ref deps) = lockfile.root.dependencies {
             for d in deps {
                 let splits_vec: Vec<_> = d.split(' ').collect();
                 if splits_vec.len() > 1 && root == splits_vec[0] {
@@ -170,7 +418,15 @@ impl DependencyTree {
                 }
             }
         }
-        panic!("Root is neither the package itself nor a direct dependency");
+        // Other workspace members share the lockfile but aren't dependencies of its root
+        if let Some(ref packages) = lockfile.package {
+            for p in packages {
+                if root == p.name {
+                    return format!("{} {}", root, p.version);
+                }
+            }
+        }
+        panic!("Root is neither the package itself, a direct dependency, nor a workspace member");
     }
 
     fn generate_tree(
@@ -185,6 +441,8 @@ impl DependencyTree {
         for raw_pac in lockfile.package.as_ref().unwrap() {
             if root == raw_pac.name.clone() + " " + &raw_pac.version {
                 let mut package = Package::new(&raw_pac.name, &raw_pac.version);
+                package.checksum = lockfile.checksum_of(raw_pac);
+                package.source = raw_pac.source.clone();
                 if depth != 0 {
                     if let Some(ref deps) = raw_pac.dependencies {
                         package.dependencies = Some(HashMap::new());
@@ -219,3 +477,157 @@ impl DependencyTree {
         panic!("Cannot find package {}", root);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use fixtures::{self, lockfile, Project, CRATES_IO};
+    use toml;
+
+    use super::*;
+
+    fn tree(packages: &[(&str, &str, &[&str])]) -> DependencyTree {
+        let mut lockfile: Lockfile = toml::from_str(&lockfile(packages)).unwrap();
+        DependencyTree::from_lockfile(&mut lockfile, None, -1)
+    }
+
+    fn status(
+        name: &str,
+        parent: Option<&str>,
+        project: &str,
+        compat: Update,
+        latest: Update,
+        path: &[&str],
+    ) -> DependencyStatus {
+        DependencyStatus {
+            kind: DependencyKind::Normal,
+            parent: parent.map(|p| p.to_owned()),
+            name: name.to_owned(),
+            requirement: None,
+            project: project.to_owned(),
+            checksum: None,
+            source: Some(CRATES_IO.to_owned()),
+            compat,
+            latest,
+            path: path.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    fn version(v: &str) -> Update {
+        Update::Version(v.to_owned())
+    }
+
+    #[test]
+    fn compare_versions_of_fixture_lockfiles() {
+        let curr = tree(&[
+            ("p", "0.1.0", &["x 1.0.0", "y 0.1.0", "z 1.0.0"]),
+            ("x", "1.0.0", &["w 0.3.0"]),
+            ("w", "0.3.0", &[]),
+            ("y", "0.1.0", &[]),
+            ("z", "1.0.0", &[]),
+        ]);
+        let compat = tree(&[
+            ("p", "0.1.0", &["x 1.2.0", "y 0.1.0", "z 1.0.0"]),
+            ("x", "1.2.0", &["w 0.3.0"]),
+            ("w", "0.3.0", &[]),
+            ("y", "0.1.0", &[]),
+            ("z", "1.0.0", &[]),
+        ]);
+        let latest = tree(&[
+            ("p", "0.1.0", &["x 2.0.0", "y 0.2.0", "z 1.0.0"]),
+            ("x", "2.0.0", &[]),
+            ("y", "0.2.0", &[]),
+            ("z", "1.0.0", &[]),
+        ]);
+        let project = Project::new();
+        project.file("Cargo.toml", "[package]\nname = \"p\"\nversion = \"0.1.0\"\n");
+        project.file("Cargo.lock", "");
+        let m = fixtures::matches(&project.args(&[]));
+        let cfg = Config::from_matches(&m).unwrap();
+        let requirements = [("x".to_owned(), "1".to_owned())].iter().cloned().collect();
+
+        let statuses = DependencyTree::compare_versions(
+            &curr,
+            &compat,
+            &latest,
+            &requirements,
+            &HashMap::new(),
+            &HashSet::new(),
+            &cfg,
+        );
+        let mut x = status("x", None, "1.0.0", version("1.2.0"), version("2.0.0"), &["p", "x"]);
+        x.requirement = Some("1".to_owned());
+        let y = status("y", None, "0.1.0", Update::Unchanged, version("0.2.0"), &["p", "y"]);
+        // Dropped by the latest x, but the current one still depends on it
+        let w = status(
+            "w",
+            Some("x"),
+            "0.3.0",
+            Update::Unchanged,
+            Update::Removed,
+            &["p", "x", "w"],
+        );
+        assert_eq!(statuses, vec![x, y, w]);
+    }
+
+    #[test]
+    fn compare_versions_without_updates() {
+        let packages: &[(&str, &str, &[&str])] =
+            &[("p", "0.1.0", &["x 1.0.0"]), ("x", "1.0.0", &[])];
+        let project = Project::new();
+        project.file("Cargo.toml", "[package]\nname = \"p\"\nversion = \"0.1.0\"\n");
+        project.file("Cargo.lock", "");
+        let m = fixtures::matches(&project.args(&[]));
+        let cfg = Config::from_matches(&m).unwrap();
+
+        let statuses = DependencyTree::compare_versions(
+            &tree(packages),
+            &tree(packages),
+            &tree(packages),
+            &HashMap::new(),
+            &HashMap::new(),
+            &HashSet::new(),
+            &cfg,
+        );
+        assert!(statuses.is_empty());
+    }
+
+    #[test]
+    fn dot_has_a_node_per_package_and_an_edge_per_dependency() {
+        let tree = tree(&[
+            ("p", "0.1.0", &["x 1.0.0", "y 0.1.0"]),
+            ("x", "1.0.0", &["y 0.1.0"]),
+            ("y", "0.1.0", &[]),
+        ]);
+        assert_eq!(
+            tree.to_dot(),
+            "digraph dependencies {\n    \
+             \"p 0.1.0\" -> \"x 1.0.0\";\n    \
+             \"p 0.1.0\" -> \"y 0.1.0\";\n    \
+             \"p 0.1.0\";\n    \
+             \"x 1.0.0\" -> \"y 0.1.0\";\n    \
+             \"x 1.0.0\";\n    \
+             \"y 0.1.0\";\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn package_delta_counts_added_and_removed_versions() {
+        let curr = tree(&[
+            ("p", "0.1.0", &["x 1.0.0", "y 0.1.0"]),
+            ("x", "1.0.0", &["w 0.3.0"]),
+            ("w", "0.3.0", &[]),
+            ("y", "0.1.0", &[]),
+        ]);
+        let latest = tree(&[
+            ("p", "0.1.0", &["x 2.0.0", "y 0.1.0"]),
+            ("x", "2.0.0", &["v 1.0.0", "u 1.0.0"]),
+            ("v", "1.0.0", &[]),
+            ("u", "1.0.0", &[]),
+            ("y", "0.1.0", &[]),
+        ]);
+        // x 2.0.0, v and u are added, x 1.0.0 and w are removed, y is in both
+        assert_eq!(DependencyTree::package_delta(&curr, &latest), (3, 2));
+        assert_eq!(DependencyTree::package_delta(&curr, &curr), (0, 0));
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::io::Read;
 use std::fs::File;
 use std::path::Path;
@@ -7,6 +8,8 @@ use error::CliResult;
 pub struct RawPackage {
     pub name: String,
     pub version: String,
+    pub source: Option<String>,
+    pub checksum: Option<String>,
     pub dependencies: Option<Vec<String>>,
 }
 
@@ -14,16 +17,38 @@ pub struct RawPackage {
 pub struct Lockfile {
     pub root: RawPackage,
     pub package: Option<Vec<RawPackage>>,
+    pub metadata: Option<HashMap<String, String>>,
 }
 
 impl Lockfile {
     pub fn from_lockfile_path<P: AsRef<Path>>(path: P) -> CliResult<Lockfile> {
-        let mut lockfile = try!(File::open(path.as_ref()));
+        let mut lockfile = File::open(path.as_ref())?;
         let mut lockfile_contents = String::new();
-        let _ = try!(lockfile.read_to_string(&mut lockfile_contents));
-        Ok(::toml::from_str(&lockfile_contents).expect(&format!(
-            "Cannot parse lockfile {}",
-            path.as_ref().to_str().unwrap()
-        )))
+        let _ = lockfile.read_to_string(&mut lockfile_contents)?;
+        Ok(::toml::from_str(&lockfile_contents).unwrap_or_else(|_| {
+            panic!("Cannot parse lockfile {}", path.as_ref().display())
+        }))
+    }
+
+    /// The checksum of a registry package, from either the package itself or the `[metadata]`
+    /// table used by older lockfiles
+    pub fn checksum_of(&self, package: &RawPackage) -> Option<String> {
+        if package.checksum.is_some() {
+            return package.checksum.clone();
+        }
+        let source = match package.source {
+            Some(ref source) => source,
+            None => return None,
+        };
+        self.metadata.as_ref().and_then(|m| {
+            m.get(&format!(
+                "checksum {} {} ({})",
+                package.name,
+                package.version,
+                source
+            )).cloned()
+        })
+            // Packages without a checksum, such as git dependencies, may be listed as `<none>`
+            .and_then(|c| if c == "<none>" { None } else { Some(c) })
     }
 }
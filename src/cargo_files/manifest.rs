@@ -1,9 +1,505 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use toml::Value;
 use toml::value::Table;
 
+use error::{CliError, CliResult};
+use target::Target;
+use util;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Manifest {
+    // Must come first as it's serialized as a top level key rather than a table
+    #[serde(rename = "cargo-features", skip_serializing_if = "Option::is_none")]
+    pub cargo_features: Option<Vec<String>>,
     pub package: Table,
-    #[serde(serialize_with = "::toml::ser::tables_last")]
+    // Dependency tables are written by `TempProject` to keep table dependencies inline
+    #[serde(default, skip_serializing)]
     pub dependencies: Table,
+    #[serde(rename = "dev-dependencies", default, skip_serializing)]
+    pub dev_dependencies: Table,
+    #[serde(rename = "build-dependencies", default, skip_serializing)]
+    pub build_dependencies: Table,
+    #[serde(default, skip_serializing)]
+    pub features: Table,
+    /// Platform specific dependency tables, keyed by target triple or `cfg(...)` expression
+    #[serde(default, skip_serializing)]
+    pub target: Table,
+    /// Overrides of registry or git dependencies, keyed by the source they override
+    #[serde(default, skip_serializing)]
+    pub patch: Table,
     pub bin: Option<Vec<Table>>,
 }
+
+impl Manifest {
+    pub fn from_manifest_path<P: AsRef<Path>>(path: P) -> CliResult<Manifest> {
+        parse_manifest(path.as_ref())
+    }
+
+    /// Parses a manifest which isn't in a file, e.g. a committed revision, naming it `origin` in
+    /// errors
+    pub fn from_contents(contents: &str, origin: &str) -> CliResult<Manifest> {
+        parse_contents(contents, origin)
+    }
+
+    /// The package name
+    pub fn name(&self) -> Option<&str> {
+        match self.package.get("name") {
+            Some(Value::String(name)) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// The edition, which is 2015 when the manifest doesn't set one
+    ///
+    /// An edition inherited with `edition.workspace = true` is read from the workspace containing
+    /// `manifest_path`, or is `None` if that can't be found.
+    pub fn edition<P: AsRef<Path>>(&self, manifest_path: P) -> Option<String> {
+        match self.package.get("edition") {
+            Some(Value::String(edition)) => Some(edition.clone()),
+            Some(Value::Table(_)) => manifest_path
+                .as_ref()
+                .parent()
+                .and_then(|dir| workspace_package_field(dir, "edition"))
+                .and_then(|edition| edition.as_str().map(|e| e.to_owned())),
+            _ => Some("2015".to_owned()),
+        }
+    }
+
+    /// Each dependency table along with the kind of dependency it declares
+    pub fn dependency_tables(&self) -> Vec<(DependencyKind, &Table)> {
+        vec![
+            (DependencyKind::Normal, &self.dependencies),
+            (DependencyKind::Development, &self.dev_dependencies),
+            (DependencyKind::Build, &self.build_dependencies),
+        ]
+    }
+
+    /// The version requirement of each dependency, by package name since that's how the lockfile
+    /// knows it
+    ///
+    /// Dependencies without a `version` key (e.g. `path` or `git` only) are omitted, and one
+    /// declared in more than one table gets the first requirement of normal, dev and build.
+    pub fn requirements(&self) -> HashMap<String, String> {
+        let mut requirements = HashMap::new();
+        for (_, deps) in self.dependency_tables() {
+            for (dep_name, dep_pac) in deps {
+                let req = match *dep_pac {
+                    Value::String(ref v) => v,
+                    Value::Table(ref t) => match t.get("version") {
+                        Some(Value::String(v)) => v,
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+                requirements
+                    .entry(package_name(dep_name, dep_pac).to_owned())
+                    .or_insert_with(|| req.clone());
+            }
+        }
+        requirements
+    }
+
+    /// The names of the packages declared in the dependency tables
+    pub fn declared(&self) -> HashSet<String> {
+        let mut declared = HashSet::new();
+        for (_, deps) in self.dependency_tables() {
+            for (dep_name, dep_pac) in deps {
+                declared.insert(package_name(dep_name, dep_pac).to_owned());
+            }
+        }
+        declared
+    }
+
+    /// The names of the packages overridden by a `[patch]` table
+    pub fn patched(&self) -> HashSet<String> {
+        let mut patched = HashSet::new();
+        for deps in self.patch.values() {
+            if let Value::Table(ref deps) = *deps {
+                for (dep_name, dep_pac) in deps {
+                    patched.insert(package_name(dep_name, dep_pac).to_owned());
+                }
+            }
+        }
+        patched
+    }
+
+    /// Declared dependencies which look unused, along with why
+    ///
+    /// `resolved` holds the names of the packages the lockfile resolves as direct dependencies of
+    /// this one. Cargo resolves the lockfile with every feature enabled, so optional dependencies
+    /// are also reported when no feature in `[features]` turns them on.
+    pub fn unused_dependencies(
+        &self,
+        resolved: &HashSet<String>,
+    ) -> Vec<(DependencyKind, String, &'static str)> {
+        let mut unused = vec![];
+        for (kind, deps) in self.dependency_tables() {
+            for (dep_name, dep_pac) in deps {
+                if !resolved.contains(package_name(dep_name, dep_pac)) {
+                    unused.push((kind, dep_name.clone(), "not in Cargo.lock"));
+                } else if is_optional(dep_pac) && !self.enables(dep_name) {
+                    unused.push((kind, dep_name.clone(), "optional and not enabled by any feature"));
+                }
+            }
+        }
+        unused
+    }
+
+    /// Moves the dependencies declared for `target` into the main dependency tables
+    ///
+    /// Returns the names of the dependencies which are only declared for other platforms.
+    pub fn merge_target(&mut self, target: &Target) -> HashSet<String> {
+        let mut others = HashSet::new();
+        for (key, platform) in &self.target {
+            let matches = target.matches(key);
+            for kind in &[
+                DependencyKind::Normal,
+                DependencyKind::Development,
+                DependencyKind::Build,
+            ] {
+                let deps = match platform.get(kind.table_name()) {
+                    Some(Value::Table(deps)) => deps,
+                    _ => continue,
+                };
+                let merged = match *kind {
+                    DependencyKind::Normal => &mut self.dependencies,
+                    DependencyKind::Development => &mut self.dev_dependencies,
+                    DependencyKind::Build => &mut self.build_dependencies,
+                };
+                for (dep_name, dep_pac) in deps {
+                    if matches {
+                        merged.entry(dep_name.clone()).or_insert_with(|| dep_pac.clone());
+                    } else {
+                        others.insert(package_name(dep_name, dep_pac).to_owned());
+                    }
+                }
+            }
+        }
+        // A dependency may also be declared for every platform, or for this one
+        for (_, deps) in self.dependency_tables() {
+            for (dep_name, dep_pac) in deps {
+                others.remove(package_name(dep_name, dep_pac));
+            }
+        }
+        others
+    }
+
+    /// Whether any feature in `[features]` turns on the optional dependency `dep_name`
+    fn enables(&self, dep_name: &str) -> bool {
+        self.features.values().any(|f| match *f {
+            Value::Array(ref items) => items.iter().any(|item| match *item {
+                Value::String(ref item) => item.split('/').next() == Some(dep_name),
+                _ => false,
+            }),
+            _ => false,
+        })
+    }
+}
+
+/// The `field` of the `[workspace.package]` table of the closest workspace containing `dir`,
+/// which members inherit with `field.workspace = true`
+pub fn workspace_package_field(dir: &Path, field: &str) -> Option<Value> {
+    let mut next = Some(dir);
+    while let Some(dir) = next {
+        next = dir.parent();
+        let mut contents = String::new();
+        if File::open(dir.join("Cargo.toml"))
+            .and_then(|mut f| f.read_to_string(&mut contents))
+            .is_err()
+        {
+            continue;
+        }
+        let manifest: Value = match ::toml::from_str(&contents) {
+            Ok(manifest) => manifest,
+            Err(_) => continue,
+        };
+        if let Some(workspace) = manifest.get("workspace") {
+            return workspace
+                .get("package")
+                .and_then(|p| p.get(field))
+                .cloned();
+        }
+    }
+    None
+}
+
+/// The member directories below `dir` matched by the glob `pattern`, relative to `dir` and
+/// sorted
+fn expand_member_glob(dir: &Path, pattern: &str) -> Vec<String> {
+    let full_pattern = if dir.as_os_str().is_empty() {
+        pattern.to_owned()
+    } else {
+        format!("{}/{}", ::glob::Pattern::escape(&dir.to_string_lossy()), pattern)
+    };
+    let paths = match ::glob::glob(&full_pattern) {
+        Ok(paths) => paths,
+        Err(_) => return vec![],
+    };
+    let mut members: Vec<_> = paths
+        .filter_map(Result::ok)
+        .filter(|path| path.join("Cargo.toml").is_file())
+        .filter_map(|path| {
+            path.strip_prefix(dir)
+                .ok()
+                .map(|member| member.to_string_lossy().into_owned())
+        })
+        .collect();
+    members.sort();
+    members
+}
+
+/// Reads and deserializes the manifest at `path`
+///
+/// A manifest which can't be deserialized is a `CliError::InvalidManifest`, so a workspace can
+/// skip that member rather than stop.
+fn parse_manifest<T: DeserializeOwned>(path: &Path) -> CliResult<T> {
+    let mut manifest_contents = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut manifest_contents))
+        .map_err(|e| CliError::Io(format!("{}: {}", path.display(), e)))?;
+    parse_contents(&manifest_contents, &path.display().to_string())
+}
+
+/// Deserializes the `contents` of a manifest, naming it `origin` in errors
+fn parse_contents<T: DeserializeOwned>(contents: &str, origin: &str) -> CliResult<T> {
+    ::toml::from_str(contents).map_err(|e| {
+        CliError::InvalidManifest(format!("Cannot parse manifest {}: {}", origin, e))
+    })
+}
+
+/// The name of the package a dependency refers to, which differs from the key it's declared
+/// under when it's renamed with `package = "..."`
+pub fn package_name<'a>(dep_name: &'a str, dep_pac: &'a Value) -> &'a str {
+    match dep_pac.get("package") {
+        Some(Value::String(name)) => name,
+        _ => dep_name,
+    }
+}
+
+/// Whether a dependency is declared with `optional = true`
+fn is_optional(dep_pac: &Value) -> bool {
+    dep_pac.get("optional").and_then(Value::as_bool) == Some(true)
+}
+
+/// The table a direct dependency was declared in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DependencyKind {
+    Normal,
+    Development,
+    Build,
+}
+
+impl DependencyKind {
+    /// The manifest table dependencies of this kind are declared in
+    pub fn table_name(&self) -> &'static str {
+        match *self {
+            DependencyKind::Normal => "dependencies",
+            DependencyKind::Development => "dev-dependencies",
+            DependencyKind::Build => "build-dependencies",
+        }
+    }
+
+    /// A short name for the kind, as shown in the `Kind` column
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            DependencyKind::Normal => "normal",
+            DependencyKind::Development => "dev",
+            DependencyKind::Build => "build",
+        }
+    }
+
+    /// The heading used when output is grouped by dependency kind
+    pub fn heading(&self) -> &'static str {
+        match *self {
+            DependencyKind::Normal => "Dependencies",
+            DependencyKind::Development => "Dev dependencies",
+            DependencyKind::Build => "Build dependencies",
+        }
+    }
+}
+
+/// The parts of a `Cargo.toml` needed to discover workspace members
+///
+/// Unlike `Manifest` this also accepts virtual manifests, which have a `[workspace]` table but no
+/// `[package]`.
+#[derive(Debug, Deserialize)]
+pub struct WorkspaceManifest {
+    pub package: Option<Table>,
+    pub workspace: Option<Workspace>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Workspace {
+    pub members: Option<Vec<String>>,
+    #[serde(rename = "default-members")]
+    pub default_members: Option<Vec<String>>,
+    /// Paths left out of the workspace, even when a glob in `members` matches them
+    pub exclude: Option<Vec<String>>,
+}
+
+impl WorkspaceManifest {
+    pub fn from_manifest_path<P: AsRef<Path>>(path: P) -> CliResult<WorkspaceManifest> {
+        parse_manifest(path.as_ref())
+    }
+
+    /// The package name, or `None` for a virtual manifest
+    pub fn name(&self) -> Option<&str> {
+        match self.package.as_ref().and_then(|p| p.get("name")) {
+            Some(Value::String(name)) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Paths of the workspace members, relative to the directory of this manifest at
+    /// `manifest_path`
+    ///
+    /// Globs such as `crates/*` are expanded to the directories they match which contain a
+    /// `Cargo.toml`, leaving out the ones listed in `exclude`.
+    pub fn members<P: AsRef<Path>>(&self, manifest_path: P) -> Vec<String> {
+        let workspace = match self.workspace {
+            Some(ref workspace) => workspace,
+            None => return vec![],
+        };
+        let dir = manifest_path.as_ref().parent().unwrap_or_else(|| Path::new(""));
+        let mut members = vec![];
+        for member in workspace.members.clone().unwrap_or_default() {
+            if util::is_glob(&member) {
+                members.extend(expand_member_glob(dir, &member));
+            } else {
+                members.push(member);
+            }
+        }
+        if let Some(ref exclude) = workspace.exclude {
+            let excluded = |member: &String| {
+                exclude
+                    .iter()
+                    .any(|e| Path::new(e.trim_end_matches('/')) == Path::new(member))
+            };
+            members.retain(|member| !excluded(member));
+        }
+        members
+    }
+
+    /// The members checked by `--workspace` unless `--all-members` is used
+    ///
+    /// These are the `default-members` of the workspace if it lists any, otherwise every member.
+    pub fn default_members<P: AsRef<Path>>(&self, manifest_path: P) -> Vec<String> {
+        self.workspace
+            .as_ref()
+            .and_then(|w| w.default_members.clone())
+            .unwrap_or_else(|| self.members(manifest_path))
+    }
+
+    /// Path to the `Cargo.toml` of a member, given the path of this manifest
+    pub fn member_manifest_path<P: AsRef<Path>>(manifest_path: P, member: &str) -> PathBuf {
+        manifest_path
+            .as_ref()
+            .parent()
+            .map(|p| p.join(member))
+            .unwrap_or_else(|| PathBuf::from(member))
+            .join("Cargo.toml")
+    }
+
+    /// Finds the manifest of the member whose package is called `name`
+    pub fn find_member<P: AsRef<Path>>(
+        &self,
+        manifest_path: P,
+        name: &str,
+    ) -> CliResult<Option<PathBuf>> {
+        for member in self.members(manifest_path.as_ref()) {
+            let member_manifest = Self::member_manifest_path(manifest_path.as_ref(), &member);
+            if Self::from_manifest_path(&member_manifest)?.name() == Some(name) {
+                return Ok(Some(member_manifest));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::io::Write;
+
+    use tempdir::TempDir;
+
+    use super::*;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        File::create(path)
+            .unwrap()
+            .write_all(contents.as_bytes())
+            .unwrap();
+    }
+
+    #[test]
+    fn glob_members_are_expanded() {
+        let dir = TempDir::new("workspace").unwrap();
+        let manifest = dir.path().join("Cargo.toml");
+        write(
+            &manifest,
+            "[workspace]\nmembers = [\"crates/*\", \"tool\"]\nexclude = [\"crates/c/\"]\n",
+        );
+        for member in &["crates/b", "crates/a", "crates/c", "tool"] {
+            write(&dir.path().join(member).join("Cargo.toml"), "[package]\n");
+        }
+        // Directories without a manifest aren't members
+        fs::create_dir_all(dir.path().join("crates/docs")).unwrap();
+
+        let workspace = WorkspaceManifest::from_manifest_path(&manifest).unwrap();
+        assert_eq!(
+            workspace.members(&manifest),
+            vec!["crates/a", "crates/b", "tool"]
+        );
+        assert_eq!(
+            workspace.default_members(&manifest),
+            workspace.members(&manifest)
+        );
+    }
+
+    #[test]
+    fn no_workspace_no_members() {
+        let dir = TempDir::new("project").unwrap();
+        let manifest = dir.path().join("Cargo.toml");
+        write(&manifest, "[package]\nname = \"p\"\n");
+        let workspace = WorkspaceManifest::from_manifest_path(&manifest).unwrap();
+        assert!(workspace.members(&manifest).is_empty());
+    }
+
+    #[test]
+    fn editions() {
+        let dir = TempDir::new("workspace").unwrap();
+        write(
+            &dir.path().join("Cargo.toml"),
+            "[workspace]\nmembers = [\"*\"]\n[workspace.package]\nedition = \"2021\"\n",
+        );
+        let edition = |package: &str| {
+            let manifest = dir.path().join("member").join("Cargo.toml");
+            write(&manifest, &format!("[package]\nname = \"m\"\n{}", package));
+            Manifest::from_manifest_path(&manifest).unwrap().edition(&manifest)
+        };
+        assert_eq!(edition("edition = \"2018\"\n"), Some("2018".to_owned()));
+        assert_eq!(edition("edition.workspace = true\n"), Some("2021".to_owned()));
+        assert_eq!(edition(""), Some("2015".to_owned()));
+
+        let outside = TempDir::new("project").unwrap();
+        let manifest = outside.path().join("Cargo.toml");
+        write(&manifest, "[package]\nname = \"p\"\nedition.workspace = true\n");
+        assert_eq!(Manifest::from_manifest_path(&manifest).unwrap().edition(&manifest), None);
+    }
+
+    #[test]
+    fn renamed_dependencies() {
+        let deps: Table = ::toml::from_str(
+            "json = { package = \"serde_json\", version = \"1\" }\nlog = \"0.4\"\n",
+        ).unwrap();
+        assert_eq!(package_name("json", &deps["json"]), "serde_json");
+        assert_eq!(package_name("log", &deps["log"]), "log");
+    }
+}
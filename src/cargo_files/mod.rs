@@ -2,6 +2,7 @@ mod manifest;
 mod lockfile;
 mod dependency_tree;
 
-pub use self::manifest::Manifest;
+pub use self::manifest::{package_name, workspace_package_field, DependencyKind, Manifest,
+                         WorkspaceManifest};
 pub use self::lockfile::Lockfile;
-pub use self::dependency_tree::{DependencyTree, Package};
+pub use self::dependency_tree::{DependencyStatus, DependencyTree, Update};
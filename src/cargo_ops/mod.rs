@@ -1,75 +1,289 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use std::fs::{self, File};
-use std::io::{self, Read, Write};
+use std::io::{self, Write};
 use std::process;
-use std::error::Error;
 
 use tempdir::TempDir;
 use toml::Value;
 use toml::value::Table;
 
 use error::{CliError, CliResult};
-use cargo_files::Manifest;
+use cargo_files::{self, DependencyKind, Manifest};
+use target::Target;
+use version;
 
 #[derive(Debug)]
 pub struct TempProject {
     pub manifest: PathBuf,
     pub lockfile: PathBuf,
+    /// The directory of the original manifest, which relative paths in it are relative to
+    orig_dir: PathBuf,
     parsed_manifest: Manifest,
-    temp_dir: TempDir,
+    /// Only held so a random directory is removed along with the project
+    #[allow(dead_code)]
+    temp_dir: TempLocation,
+    /// The `cargo` binary which runs `cargo update`
+    cargo: PathBuf,
+    /// The seconds `cargo update` waits on the registry, `None` for cargo's own default
+    http_timeout: Option<u64>,
+    /// The index which replaces crates.io, see `use_registry_index`
+    registry_index: Option<String>,
+}
+
+/// The file marking a directory named with `--temp-name` as one this tool may reuse
+const TEMP_MARKER: &str = ".cargo-outdated";
+
+/// Where the temporary project is written
+#[derive(Debug)]
+enum TempLocation {
+    /// A randomly named directory, removed when the project is dropped
+    Random(TempDir),
+    /// A directory named with `--temp-name`, kept after the run so it can be inspected
+    Named(PathBuf),
+}
+
+impl TempLocation {
+    fn path(&self) -> &Path {
+        match *self {
+            TempLocation::Random(ref dir) => dir.path(),
+            TempLocation::Named(ref dir) => dir,
+        }
+    }
 }
 
 impl TempProject {
-    pub fn new<P: AsRef<Path>>(orig_manifest: P, orig_lockfile: P) -> CliResult<TempProject> {
-        let temp_dir = try!(TempDir::new("cargo-outdated"));
+    /// Copies the project into a new temporary directory, called `temp_name` in the system's
+    /// temporary directory when given and randomly named otherwise
+    ///
+    /// A named directory left by an earlier run is emptied and reused, but one which wasn't
+    /// created by this tool is never touched.
+    pub fn new<P: AsRef<Path>>(
+        orig_manifest: P,
+        orig_lockfile: P,
+        temp_name: Option<&OsStr>,
+    ) -> CliResult<TempProject> {
+        let temp_dir = match temp_name {
+            Some(name) => {
+                let dir = env::temp_dir().join(name);
+                let marker = dir.join(TEMP_MARKER);
+                if dir.exists() {
+                    if !marker.exists() {
+                        return Err(CliError::Generic(format!(
+                            "The temporary directory {} already exists and wasn't created by \
+                             cargo outdated, remove it or choose another --temp-name",
+                            dir.display()
+                        )));
+                    }
+                    fs::remove_dir_all(&dir).map_err(|e| file_error(&dir, e))?;
+                }
+                fs::create_dir(&dir).map_err(|e| file_error(&dir, e))?;
+                File::create(&marker).map_err(|e| file_error(&marker, e))?;
+                TempLocation::Named(dir)
+            }
+            None => TempLocation::Random(TempDir::new("cargo-outdated")?),
+        };
         let manifest = temp_dir.path().join("Cargo.toml");
         let lockfile = temp_dir.path().join("Cargo.lock");
 
-        let mut buf = String::new();
-        let mut orig_manifest_file = try!(File::open(&orig_manifest));
-        try!(orig_manifest_file.read_to_string(&mut buf));
-        let parsed_manifest: Manifest = ::toml::from_str(&buf).expect("Cannot parse Cargo.toml");
-        try!(fs::copy(&orig_lockfile, &lockfile));
+        let orig_manifest = orig_manifest.as_ref();
+        let orig_lockfile = orig_lockfile.as_ref();
+        let mut parsed_manifest = Manifest::from_manifest_path(orig_manifest)?;
+        let orig_dir = fs::canonicalize(orig_manifest)
+            .map(|p| p.parent().map(Path::to_path_buf).unwrap_or_default())
+            .map_err(|e| file_error(orig_manifest, e))?;
+        // The temporary project isn't part of the workspace, so it can't inherit the version
+        let inherits_version = match parsed_manifest.package.get("version") {
+            Some(Value::Table(t)) => t.get("workspace").and_then(Value::as_bool) == Some(true),
+            _ => false,
+        };
+        if inherits_version {
+            if let Some(version) = cargo_files::workspace_package_field(&orig_dir, "version") {
+                parsed_manifest.package.insert("version".to_owned(), version);
+            }
+        }
+        fs::copy(orig_lockfile, &lockfile).map_err(|e| file_error(orig_lockfile, e))?;
+        // The written manifests declare a `test` binary, whose source cargo may check exists
+        let stub = temp_dir.path().join("test.rs");
+        File::create(&stub).map_err(|e| file_error(&stub, e))?;
 
         Ok(TempProject {
-            manifest: manifest,
-            lockfile: lockfile,
-            parsed_manifest: parsed_manifest,
-            temp_dir: temp_dir,
+            manifest,
+            lockfile,
+            orig_dir,
+            parsed_manifest,
+            temp_dir,
+            cargo: PathBuf::from("cargo"),
+            http_timeout: None,
+            registry_index: None,
         })
     }
 
+    /// Runs `cargo update` with the binary at `cargo` instead of the one on the `PATH`
+    pub fn use_cargo(&mut self, cargo: &Path) {
+        self.cargo = cargo.to_path_buf();
+    }
+
+    /// Gives up on the registry after `secs` seconds in any later `cargo update`
+    pub fn use_http_timeout(&mut self, secs: u64) {
+        self.http_timeout = Some(secs);
+    }
+
     pub fn cargo_update(&self) -> CliResult<()> {
-        if let Err(e) = process::Command::new("cargo")
+        let mut command = process::Command::new(&self.cargo);
+        command
             .arg("update")
             .arg("--manifest-path")
-            .arg(
-                self.manifest
-                    .to_str()
-                    .expect("failed to convert temp Cargo.toml path to string"),
-            )
-            .output()
-            .and_then(|v| if v.status.success() {
-                Ok(v)
-            } else {
-                Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "did not exit successfully",
-                ))
-            }) {
+            .arg(&self.manifest);
+        if let Some(ref url) = self.registry_index {
+            // Only crates.io is replaced, the project's own `.cargo/config` still applies
+            command
+                .arg("--config")
+                .arg("source.crates-io.replace-with=\"outdated-registry\"")
+                .arg("--config")
+                .arg(format!("source.outdated-registry.registry={}", Value::String(url.clone())));
+        }
+        if let Some(secs) = self.http_timeout {
+            command.env("CARGO_HTTP_TIMEOUT", secs.to_string());
+        }
+        let output = match command.output() {
+            Ok(output) => output,
+            Err(e) => {
+                return Err(CliError::Generic(format!(
+                    "Failed to run 'cargo update' with error '{}'",
+                    e
+                )))
+            }
+        };
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // Only one package may set a given `links` key, which the newer versions can break
+        if let Some(line) = stderr.lines().find(|l| l.contains("native library")) {
             return Err(CliError::Generic(format!(
-                "Failed to run 'cargo update' with error '{}'",
-                e.description()
+                "Failed to run 'cargo update': the updated packages link the same native library \
+                 more than once, which cargo doesn't allow ({})",
+                line.trim()
             )));
         }
-        Ok(())
+        Err(CliError::Generic(format!(
+            "Failed to run 'cargo update' with error '{}'",
+            stderr.trim()
+        )))
+    }
+
+    /// Replaces crates.io with the registry index at `url` for any later `cargo update`
+    ///
+    /// Sparse indexes (`sparse+https://...`) are fetched over HTTP by cargo itself, which
+    /// rejects them unless the URL ends with a `/`.
+    pub fn use_registry_index(&mut self, url: &str) {
+        self.registry_index = Some(if url.starts_with("sparse+") && !url.ends_with('/') {
+            format!("{}/", url)
+        } else {
+            url.to_owned()
+        });
+    }
+
+    /// Resolves the dependencies declared for `target` along with the ones for every platform
+    ///
+    /// Returns the names of the dependencies which are only declared for other platforms.
+    pub fn use_target(&mut self, target: &Target) -> HashSet<String> {
+        self.parsed_manifest.merge_target(target)
+    }
+
+    /// Gives the package `version` in the manifests written from now on, e.g. to see how the
+    /// dependencies resolve once a pending release is published
+    pub fn assume_version(&mut self, version: &str) {
+        self.parsed_manifest
+            .package
+            .insert("version".to_owned(), Value::String(version.to_owned()));
+    }
+
+    /// The version requirement of each direct dependency, as written in the original manifest
+    ///
+    /// Dependencies without a `version` key (e.g. `path` or `git` only) are omitted.
+    pub fn requirements(&self) -> HashMap<String, String> {
+        self.parsed_manifest.requirements()
+    }
+
+    /// The names of the packages declared in the original manifest, including those for the
+    /// platform given to `use_target`
+    pub fn declared(&self) -> HashSet<String> {
+        self.parsed_manifest.declared()
+    }
+
+    /// The names of the packages overridden by a `[patch]` in the original manifest
+    pub fn patched(&self) -> HashSet<String> {
+        self.parsed_manifest.patched()
+    }
+
+    /// The kind of each direct dependency, by package name
+    ///
+    /// A dependency declared in more than one table is reported as the first of normal, dev and
+    /// build.
+    pub fn kinds(&self) -> HashMap<String, DependencyKind> {
+        let mut kinds = HashMap::new();
+        for (kind, deps) in self.parsed_manifest.dependency_tables() {
+            for (dep_name, dep_pac) in deps {
+                kinds
+                    .entry(cargo_files::package_name(dep_name, dep_pac).to_owned())
+                    .or_insert(kind);
+            }
+        }
+        kinds
     }
 
     fn write_manifest(&self, contents: &Manifest) -> CliResult<()> {
-        let mut file = try!(File::create(&self.manifest));
         let serialized = ::toml::to_string(contents).expect("Failed to serialized Cargo.toml");
-        try!(write!(file, "{}", serialized));
-        Ok(())
+        let write = || -> io::Result<()> {
+            let mut file = File::create(&self.manifest)?;
+            write!(file, "{}", serialized)?;
+            // The serializer would expand table dependencies into `[dependencies.foo]` sections,
+            // so they're written by hand to keep them inline as they usually are in a manifest
+            for (kind, deps) in contents.dependency_tables() {
+                write!(file, "\n[{}]\n", kind.table_name())?;
+                for (dep_name, dep_pac) in deps {
+                    let dep_pac = self.absolute_path(dep_pac);
+                    writeln!(file, "{} = {}", toml_key(dep_name), inline_value(&dep_pac))?;
+                }
+            }
+            // Patches stay in place so patched packages resolve to the same source as before
+            for (source, deps) in &contents.patch {
+                let deps = match *deps {
+                    Value::Table(ref deps) => deps,
+                    _ => continue,
+                };
+                write!(file, "\n[patch.{}]\n", toml_key(source))?;
+                for (dep_name, dep_pac) in deps {
+                    let dep_pac = self.absolute_path(dep_pac);
+                    writeln!(file, "{} = {}", toml_key(dep_name), inline_value(&dep_pac))?;
+                }
+            }
+            Ok(())
+        };
+        write().map_err(|e| file_error(&self.manifest, e))
+    }
+
+    /// The dependency `dep_pac` with its `path` made absolute, since the temporary manifest is no
+    /// longer next to the paths it was relative to
+    fn absolute_path(&self, dep_pac: &Value) -> Value {
+        let mut dep_pac = dep_pac.clone();
+        if let Value::Table(ref mut t) = dep_pac {
+            let path = match t.get("path") {
+                Some(Value::String(p)) => Some(self.orig_dir.join(p)),
+                _ => None,
+            };
+            if let Some(path) = path {
+                t.insert(
+                    "path".to_owned(),
+                    Value::String(path.to_string_lossy().into_owned()),
+                );
+            }
+        }
+        dep_pac
     }
 
     pub fn write_manifest_semver(&self) -> CliResult<()> {
@@ -89,16 +303,26 @@ impl TempProject {
         bin.insert("path".to_owned(), Value::String("test.rs".to_owned()));
 
         let manifest_semver = Manifest {
-            package: package,
+            cargo_features: self.parsed_manifest.cargo_features.clone(),
+            package,
             dependencies: self.parsed_manifest.dependencies.clone(),
+            dev_dependencies: self.parsed_manifest.dev_dependencies.clone(),
+            build_dependencies: self.parsed_manifest.build_dependencies.clone(),
+            features: Table::new(),
+            target: Table::new(),
+            patch: self.parsed_manifest.patch.clone(),
             bin: Some(vec![bin]),
         };
-        try!(self.write_manifest(&manifest_semver));
+        self.write_manifest(&manifest_semver)?;
 
         Ok(())
     }
 
-    pub fn write_manifest_latest(&self) -> CliResult<()> {
+    /// Writes the manifest used to find the latest versions
+    ///
+    /// Requirements are replaced with `*`, or when `compatible` is set with the widest caret
+    /// requirement that is still SemVer compatible with the original one.
+    pub fn write_manifest_latest(&self, compatible: bool) -> CliResult<()> {
         let name = self.parsed_manifest
             .package
             .get("name")
@@ -110,31 +334,333 @@ impl TempProject {
         let mut package = Table::new();
         package.insert("name".to_owned(), name.clone());
         package.insert("version".to_owned(), version.clone());
-        let mut dependencies = Table::new();
-        for (dep_name, dep_pac) in &self.parsed_manifest.dependencies {
-            match *dep_pac {
-                Value::Table(ref t) => {
-                    let mut t = t.clone();
-                    t.insert("version".to_owned(), Value::String("*".to_owned()));
-                    let _ = dependencies.insert(dep_name.clone(), Value::Table(t));
-                }
-                Value::String(_) => {
-                    let _ = dependencies.insert(dep_name.clone(), Value::String("*".to_owned()));
-                }
-                _ => unreachable!(),
-            }
-        }
         let mut bin = Table::new();
         bin.insert("name".to_owned(), Value::String("test".to_owned()));
         bin.insert("path".to_owned(), Value::String("test.rs".to_owned()));
 
-        let manifest_semver = Manifest {
-            package: package,
-            dependencies: dependencies,
+        let manifest_latest = Manifest {
+            cargo_features: self.parsed_manifest.cargo_features.clone(),
+            package,
+            dependencies: replace_version_with_wildcard(
+                &self.parsed_manifest.dependencies,
+                compatible,
+            ),
+            dev_dependencies: replace_version_with_wildcard(
+                &self.parsed_manifest.dev_dependencies,
+                compatible,
+            ),
+            build_dependencies: replace_version_with_wildcard(
+                &self.parsed_manifest.build_dependencies,
+                compatible,
+            ),
+            features: Table::new(),
+            target: Table::new(),
+            patch: self.parsed_manifest.patch.clone(),
             bin: Some(vec![bin]),
         };
-        try!(self.write_manifest(&manifest_semver));
+        self.write_manifest(&manifest_latest)?;
 
         Ok(())
     }
 }
+
+/// Replaces the requirement of every dependency, see `write_manifest_latest`
+///
+/// Dependencies declared as `[dependencies.foo]` sections parse to the same table as the inline
+/// `foo = { ... }` form, so both keep their other keys, e.g. `features`, and get a `version` even
+/// when they only had a `path`.
+///
+/// A `git` dependency's latest version is whatever its branch, tag or rev has, so any `version`
+/// next to it, which cargo only checks when publishing, is dropped rather than replaced.
+fn replace_version_with_wildcard(dependencies: &Table, compatible: bool) -> Table {
+    let wildcard = |requirement: Option<&Value>| match requirement {
+        Some(Value::String(r)) if compatible => version::compatible_requirement(r),
+        _ => "*".to_owned(),
+    };
+    let mut wildcarded = Table::new();
+    for (dep_name, dep_pac) in dependencies {
+        match *dep_pac {
+            Value::Table(ref t) => {
+                let mut t = t.clone();
+                if t.contains_key("git") {
+                    t.remove("version");
+                } else {
+                    let version = wildcard(t.get("version"));
+                    t.insert("version".to_owned(), Value::String(version));
+                }
+                let _ = wildcarded.insert(dep_name.clone(), Value::Table(t));
+            }
+            Value::String(_) => {
+                let version = wildcard(Some(dep_pac));
+                let _ = wildcarded.insert(dep_name.clone(), Value::String(version));
+            }
+            _ => unreachable!(),
+        }
+    }
+    wildcarded
+}
+
+/// Runs `cargo update` on the real project to move `name` from `from` to exactly `to`
+///
+/// `http_timeout` is the seconds to wait on the registry, `None` for cargo's own default.
+pub fn cargo_update_precise(
+    cargo: &Path,
+    http_timeout: Option<u64>,
+    manifest: &Path,
+    name: &str,
+    from: &str,
+    to: &str,
+) -> CliResult<()> {
+    let mut command = process::Command::new(cargo);
+    command
+        .arg("update")
+        .arg("--manifest-path")
+        .arg(manifest)
+        .arg("-p")
+        .arg(format!("{}:{}", name, from))
+        .arg("--precise")
+        .arg(to);
+    if let Some(secs) = http_timeout {
+        command.env("CARGO_HTTP_TIMEOUT", secs.to_string());
+    }
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(CliError::Generic(format!(
+            "Failed to update {} to {}: {}",
+            name,
+            to,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// An I/O error on `path`, naming the path since the error itself doesn't
+fn file_error(path: &Path, e: io::Error) -> CliError {
+    CliError::Io(format!("{}: {}", path.display(), e))
+}
+
+/// Renders a value on a single line, using inline tables for any nested tables
+fn inline_value(value: &Value) -> String {
+    match *value {
+        Value::Table(ref t) => {
+            let entries: Vec<_> = t.iter()
+                .map(|(k, v)| format!("{} = {}", toml_key(k), inline_value(v)))
+                .collect();
+            format!("{{ {} }}", entries.join(", "))
+        }
+        Value::Array(ref a) => {
+            let values: Vec<_> = a.iter().map(inline_value).collect();
+            format!("[{}]", values.join(", "))
+        }
+        ref v => v.to_string(),
+    }
+}
+
+/// Quotes a key unless it's a valid bare key
+fn toml_key(key: &str) -> String {
+    let is_bare = !key.is_empty() &&
+        key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if is_bare {
+        key.to_owned()
+    } else {
+        Value::String(key.to_owned()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn bare_keys() {
+        assert_eq!(toml_key("serde_json"), "serde_json");
+        assert_eq!(toml_key("foo-bar2"), "foo-bar2");
+    }
+
+    #[test]
+    fn quoted_keys() {
+        assert_eq!(toml_key(""), "\"\"");
+        assert_eq!(toml_key("crates-io.example"), "\"crates-io.example\"");
+        assert_eq!(toml_key("https://example.com/a"), "\"https://example.com/a\"");
+        assert_eq!(toml_key("caf\u{e9}"), "\"caf\u{e9}\"");
+    }
+
+    #[test]
+    fn paths_are_absolute_in_every_dependency_table() {
+        let dir = TempDir::new("project").unwrap();
+        let manifest = dir.path().join("Cargo.toml");
+        let lockfile = dir.path().join("Cargo.lock");
+        File::create(&manifest)
+            .unwrap()
+            .write_all(
+                b"[package]\nname = \"p\"\nversion = \"0.1.0\"\n\
+                  [dependencies]\na = { path = \"../a\" }\n\
+                  [dev-dependencies]\nb = { path = \"b\", version = \"1\" }\n\
+                  [build-dependencies]\nc = { path = \"/c\" }\n\
+                  [patch.crates-io]\nd = { path = \"d\" }\n",
+            )
+            .unwrap();
+        File::create(&lockfile).unwrap();
+
+        let project = TempProject::new(&manifest, &lockfile, None).unwrap();
+        project.write_manifest_semver().unwrap();
+        let mut written = String::new();
+        File::open(&project.manifest)
+            .unwrap()
+            .read_to_string(&mut written)
+            .unwrap();
+        let written = Manifest::from_contents(&written, "the temporary manifest").unwrap();
+
+        let orig_dir = fs::canonicalize(dir.path()).unwrap();
+        let path = |deps: &Table, name: &str| deps[name]["path"].as_str().unwrap().to_owned();
+        assert_eq!(
+            path(&written.dependencies, "a"),
+            orig_dir.join("../a").to_string_lossy()
+        );
+        assert_eq!(
+            path(&written.dev_dependencies, "b"),
+            orig_dir.join("b").to_string_lossy()
+        );
+        assert_eq!(path(&written.build_dependencies, "c"), "/c");
+        assert_eq!(
+            written.patch["crates-io"]["d"]["path"].as_str().unwrap(),
+            orig_dir.join("d").to_string_lossy()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn only_the_latest_versions_come_from_the_registry_index() {
+        let project = ::fixtures::Project::new();
+        project.file(
+            "index/config.json",
+            "{\"dl\":\"file:///nowhere\",\"api\":null}\n",
+        );
+        project.file(
+            "index/1/x",
+            "{\"name\":\"x\",\"vers\":\"1.0.0\",\"deps\":[],\"cksum\":\"\",\"features\":{},\"yanked\":false}\n\
+             {\"name\":\"x\",\"vers\":\"2.3.0\",\"deps\":[],\"cksum\":\"\",\"features\":{},\"yanked\":false}\n",
+        );
+        // Resolves `x` to the newest version in the index it's given, and to 1.0.0 otherwise
+        let cargo = project.script(
+            "fake-cargo/cargo",
+            &format!(
+                "echo \"$PWD $*\" >> '{log}'\n\
+                 index=\n\
+                 for arg in \"$@\"; do\n    \
+                 case \"$arg\" in source.outdated-registry.registry=*)\n        \
+                 index=${{arg#*file://}}; index=${{index%\\\"}};;\n    \
+                 esac\n\
+                 done\n\
+                 version=1.0.0\n\
+                 if [ -n \"$index\" ]; then\n    \
+                 version=$(sed -n 's/.*\"vers\":\"\\([^\"]*\\)\".*/\\1/p' \"$index/1/x\" | tail -n 1)\n\
+                 fi\n\
+                 printf '{lockfile}' \"$version\" \"$version\" > \"$(dirname \"$3\")/Cargo.lock\"\n",
+                log = project.path("cargo.log").display(),
+                lockfile = ::fixtures::lockfile(&[("p", "0.1.0", &["x %s"]), ("x", "%s", &[])])
+                    .replace('\n', "\\n")
+            ),
+        );
+        let manifest = project.file(
+            "Cargo.toml",
+            "[package]\nname = \"p\"\nversion = \"0.1.0\"\n[dependencies]\nx = \"1\"\n",
+        );
+        let lockfile = project.file(
+            "Cargo.lock",
+            &::fixtures::lockfile(&[("p", "0.1.0", &["x 1.0.0"]), ("x", "1.0.0", &[])]),
+        );
+        let resolved = |project: &TempProject| {
+            let lockfile = cargo_files::Lockfile::from_lockfile_path(&project.lockfile).unwrap();
+            lockfile.package.unwrap()[0].version.clone()
+        };
+
+        let mut temp = TempProject::new(&manifest, &lockfile, None).unwrap();
+        temp.use_cargo(&cargo);
+        temp.write_manifest_semver().unwrap();
+        temp.cargo_update().unwrap();
+        assert_eq!(resolved(&temp), "1.0.0");
+
+        let index = format!("file://{}", project.path("index").display());
+        temp.use_registry_index(&index);
+        temp.write_manifest_latest(false).unwrap();
+        temp.cargo_update().unwrap();
+        assert_eq!(resolved(&temp), "2.3.0");
+
+        let log = project.cargo_log();
+        assert_eq!(log.len(), 2);
+        assert!(!log[0].contains("--config"));
+        assert!(log[1].contains(&format!("source.outdated-registry.registry=\"{}\"", index)));
+        // cargo runs where this tool was started rather than in the temporary project
+        let cwd = env::current_dir().unwrap();
+        for line in &log {
+            assert!(line.starts_with(&format!("{} ", cwd.display())));
+        }
+    }
+
+    #[test]
+    fn requested_temp_name_is_used_and_reused() {
+        let project = ::fixtures::Project::new();
+        let manifest = project.file("Cargo.toml", "[package]\nname = \"p\"\nversion = \"0.1.0\"\n");
+        let lockfile = project.file("Cargo.lock", "");
+        // Unique to this run, since the temporary directory is shared with any other run
+        let mut name = project.path("").file_name().unwrap().to_owned();
+        name.push("-kept");
+        let dir = env::temp_dir().join(&name);
+
+        let temp = TempProject::new(&manifest, &lockfile, Some(&name)).unwrap();
+        assert_eq!(temp.manifest, dir.join("Cargo.toml"));
+        drop(temp);
+        assert!(dir.join("Cargo.lock").exists());
+        File::create(dir.join("left-over")).unwrap();
+
+        let temp = TempProject::new(&manifest, &lockfile, Some(&name)).unwrap();
+        assert_eq!(temp.manifest, dir.join("Cargo.toml"));
+        assert!(!dir.join("left-over").exists());
+
+        // A directory this tool didn't create is left alone
+        fs::remove_file(dir.join(TEMP_MARKER)).unwrap();
+        assert!(TempProject::new(&manifest, &lockfile, Some(&name)).is_err());
+        assert!(dir.join("Cargo.lock").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn dependency_sections_are_rewritten_with_their_features() {
+        let project = ::fixtures::Project::new();
+        let manifest = project.file(
+            "Cargo.toml",
+            "[package]\nname = \"p\"\nversion = \"0.1.0\"\n\
+             [dependencies.serde]\nversion = \"1.0\"\nfeatures = [\"derive\"]\n\
+             default-features = false\n",
+        );
+        let lockfile = project.file("Cargo.lock", "");
+        let temp = TempProject::new(&manifest, &lockfile, None).unwrap();
+        let serde = |temp: &TempProject| {
+            let written = fs::read_to_string(&temp.manifest).unwrap();
+            let written = Manifest::from_contents(&written, "the temporary manifest").unwrap();
+            written.dependencies["serde"].clone()
+        };
+        let features = Value::Array(vec![Value::String("derive".to_owned())]);
+
+        temp.write_manifest_semver().unwrap();
+        let semver = serde(&temp);
+        assert_eq!(semver["version"].as_str(), Some("1.0"));
+        assert_eq!(semver["features"], features);
+        assert_eq!(semver["default-features"].as_bool(), Some(false));
+
+        temp.write_manifest_latest(false).unwrap();
+        let latest = serde(&temp);
+        assert_eq!(latest["version"].as_str(), Some("*"));
+        assert_eq!(latest["features"], features);
+        assert_eq!(latest["default-features"].as_bool(), Some(false));
+
+        temp.write_manifest_latest(true).unwrap();
+        assert_eq!(serde(&temp)["version"].as_str(), Some("^1"));
+    }
+}
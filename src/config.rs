@@ -1,10 +1,57 @@
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 
 use clap::ArgMatches;
 
+use cargo_files::DependencyKind;
 use fmt::Format;
 use util;
-use error::CliResult;
+use target::Target;
+use version::Severity;
+use error::{self, CliError, CliResult};
+
+/// How the results are written to stdout
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// Aligned columns with a header
+    Table,
+    /// `name project compat latest` rows separated by single spaces, with no header, alignment or
+    /// color, for `grep` and `awk`
+    ///
+    /// A field only contains spaces with another `--dependency-format`, or when the source of a
+    /// dependency changed.
+    Plain,
+    /// A self-contained HTML page, e.g. to publish from CI
+    Html,
+    /// A SARIF 2.1.0 log, for code scanning services
+    Sarif,
+    /// A JSON object with the summary counts and a list of the dependencies, for scripts
+    Json,
+    /// A GitHub flavored Markdown table, e.g. for a pull request comment
+    Markdown,
+}
+
+/// The order dependencies are listed in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    /// Alphabetically
+    Name,
+    /// The most severe updates first, then alphabetically
+    Severity,
+}
+
+/// How versions are shown in the table, plain and HTML formats
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DependencyFormat {
+    /// Just the version, e.g. `1.5.0`
+    Bare,
+    /// New versions of direct dependencies along with their requirement, e.g. `^1.2.3 -> 1.5.0`
+    Requirement,
+    /// The project version along with where it came from
+    Source,
+}
 
 #[derive(Debug)]
 pub struct Config<'tu> {
@@ -13,13 +60,179 @@ pub struct Config<'tu> {
     pub depth: i32,
     pub verbose: bool,
     pub exit_code: i32,
+    /// Print why the exit code isn't 0
+    pub explain_exit_code: bool,
     pub manifest: PathBuf,
     pub lockfile: PathBuf,
+    pub workspace: bool,
+    pub keep_going: bool,
+    pub resume: bool,
+    pub all_members: bool,
+    /// Print a report per workspace member rather than one for the whole workspace
+    pub no_aggregate: bool,
+    /// How many workspace members are checked at once
+    pub jobs: usize,
+    pub group_by_kind: bool,
+    pub format: OutputFormat,
+    pub cache: bool,
+    pub suggest: bool,
+    pub registry_index: Option<&'tu str>,
+    /// The seconds cargo waits on the registry, `None` for cargo's own default
+    pub registry_timeout: Option<u64>,
+    /// The version the checked package is given in the temporary projects
+    pub assume_version: Option<&'tu str>,
+    pub sort: SortOrder,
+    pub why: bool,
+    pub color: bool,
+    pub exclude_members: Vec<&'tu str>,
+    pub changed_only: bool,
+    pub only_explicit: bool,
+    pub compatible_latest: bool,
+    pub summary_only: bool,
+    /// Leave out the keys of null values in the JSON report
+    pub json_compact_nulls: bool,
+    pub interactive: bool,
+    pub checksums: bool,
+    pub show_kind: bool,
+    /// Leave the latest version out when it's the SemVer compatible one
+    pub collapse: bool,
+    /// List the dependencies removed in the latest graph on one line rather than a row each
+    pub concise_rm: bool,
+    /// Add the repository or homepage of each dependency
+    pub links: bool,
+    pub unused: bool,
+    /// Only report updates of this severity, `None` reports all of them
+    pub only: Option<Severity>,
+    /// Only report dependencies of this kind, `None` reports all of them
+    pub only_kind: Option<DependencyKind>,
+    /// Only report path dependencies, such as other workspace members
+    pub internal_only: bool,
+    /// Only report dependencies from a registry or git
+    pub ignore_internal: bool,
+    /// The names of the dependencies left out of the report
+    pub ignore: Vec<String>,
+    pub dependency_format: DependencyFormat,
+    pub duplicates: bool,
+    pub graph_delta: bool,
+    /// Where the report is written, `None` for stdout
+    pub report_path: Option<PathBuf>,
+    /// The platform whose `[target]` dependencies are checked as well, the host unless `--target`
+    /// is given
+    pub target: Option<Target>,
+    /// Approved package versions, see the `allowlist` module
+    pub allowlist: Option<PathBuf>,
+    /// Another manifest whose requirements are compared with the project's
+    pub against: Option<PathBuf>,
+    /// Where a status badge is written, see `report::write_badge`
+    pub badge: Option<PathBuf>,
+    /// Where the dependency graphs are written in DOT format
+    pub graph_dir: Option<PathBuf>,
+    /// The `cargo` binary which resolves the dependencies
+    pub cargo: PathBuf,
+    /// The name of the kept temporary project directory, `None` for a random one
+    pub temp_name: Option<OsString>,
+    /// Where a report is written in each of `formats`
+    pub output_dir: Option<PathBuf>,
+    pub formats: Vec<OutputFormat>,
+}
+
+/// The names accepted by `--format` and `--formats`, in the order they're listed in `--help`
+pub const OUTPUT_FORMATS: &[&str] = &[
+    "table",
+    "plain",
+    "html",
+    "sarif",
+    "json",
+    "markdown",
+];
+
+/// The format called `name`, one of `OUTPUT_FORMATS`
+fn output_format(name: &str) -> OutputFormat {
+    match name {
+        "plain" => OutputFormat::Plain,
+        "html" => OutputFormat::Html,
+        "sarif" => OutputFormat::Sarif,
+        "json" => OutputFormat::Json,
+        "markdown" => OutputFormat::Markdown,
+        _ => OutputFormat::Table,
+    }
+}
+
+/// Defaults for a project read from the `.outdated.toml` next to its manifest
+///
+/// Options given on the command line take precedence.
+#[derive(Debug, Default, Deserialize)]
+struct ProjectDefaults {
+    format: Option<String>,
+    depth: Option<i32>,
+    #[serde(rename = "exit-code")]
+    exit_code: Option<i32>,
+    ignore: Option<Vec<String>>,
+}
+
+impl ProjectDefaults {
+    fn load(manifest: &Path) -> CliResult<ProjectDefaults> {
+        let path = manifest.with_file_name(".outdated.toml");
+        let mut contents = String::new();
+        match File::open(&path) {
+            Ok(mut file) => file.read_to_string(&mut contents)?,
+            Err(_) => return Ok(ProjectDefaults::default()),
+        };
+        let defaults: ProjectDefaults = ::toml::from_str(&contents).map_err(|e| {
+            CliError::Generic(format!("Cannot parse {}: {}", path.display(), e))
+        })?;
+        if let Some(ref format) = defaults.format {
+            if !OUTPUT_FORMATS.contains(&&**format) {
+                return Err(CliError::Generic(format!(
+                    "Unknown format '{}' in {}",
+                    format,
+                    path.display()
+                )));
+            }
+        }
+        if defaults.exit_code == Some(error::EXIT_ERROR) ||
+            defaults.exit_code == Some(error::EXIT_UNAPPROVED)
+        {
+            return Err(CliError::Generic(format!(
+                "The exit-code in {} can't be {} or {}, which are reserved for errors in \
+                 cargo-outdated itself and packages missing from the --allowlist",
+                path.display(),
+                error::EXIT_ERROR,
+                error::EXIT_UNAPPROVED
+            )));
+        }
+        Ok(defaults)
+    }
 }
 
 impl<'tu> Config<'tu> {
     pub fn from_matches(m: &'tu ArgMatches) -> CliResult<Self> {
-        debugln!("Config:from_matches");
+        Self::for_manifest(m, None)
+    }
+
+    /// Like `from_matches`, but for the project of `manifest` rather than the one named by
+    /// `--manifest-path`, using the `Cargo.lock` next to it
+    pub fn for_manifest(m: &'tu ArgMatches, manifest: Option<PathBuf>) -> CliResult<Self> {
+        debugln!("Config:for_manifest;manifest={:?}", manifest);
+        let globbed = manifest.is_some();
+        let lockfile = match manifest {
+            Some(ref manifest) => manifest.with_file_name("Cargo.lock"),
+            None => util::find_file(
+                m.value_of_os("lockfile-path").map_or(Path::new("Cargo.lock"), Path::new),
+                m.is_present("lockfile-path")
+            )?,
+        };
+        let manifest = match (manifest, m.value_of_os("manifest-path")) {
+            (Some(manifest), _) => manifest,
+            (None, Some(path)) => util::file_in_dir(Path::new(path), "Cargo.toml")?,
+            (None, None) => util::find_file(Path::new("Cargo.toml"), false)?,
+        };
+        let defaults = ProjectDefaults::load(&manifest)?;
+        let format = output_format(if m.occurrences_of("format") > 0 {
+            m.value_of("format").unwrap_or("table")
+        } else {
+            defaults.format.as_deref().unwrap_or("table")
+        });
         let depth = match m.value_of("depth") {
             Some(d_str) => {
                 match d_str.parse::<i32>() {
@@ -28,24 +241,140 @@ impl<'tu> Config<'tu> {
                         wlnerr!("{} Couldn't parse '{}' as a valid depth (Valid depths are 0 (infinite) to ~4,000,000,000)",
                                 Format::Error("error:"),
                                 d_str);
-                        ::std::process::exit(1);
+                        ::std::process::exit(error::EXIT_ERROR);
                     }
                 }
             }
-            None => if m.is_present("root-deps-only") { 1 } else { -1 },
+            None => if m.is_present("root-deps-only") { 1 } else { defaults.depth.unwrap_or(-1) },
         };
+        // Each project matched by a glob gets a directory of its own
+        let temp_name = m.value_of_os("temp-name").map(|name| {
+            let mut name = name.to_os_string();
+            if let (true, Some(dir)) = (globbed, manifest.parent().and_then(Path::file_name)) {
+                name.push("-");
+                name.push(dir);
+            }
+            name
+        });
 
         let cfg = Config {
             to_update: m.values_of("package").map(|v| v.collect()),
             root: m.value_of("root"),
-            depth: depth,
+            depth,
             verbose: m.is_present("verbose"),
             exit_code: {
                 debugln!("Config:from_matches:exit-code={:?}", m.value_of("exit-code"));
-                value_t!(m, "exit-code", i32).unwrap_or(0)
+                if m.is_present("no-exit-code") {
+                    0
+                } else if m.occurrences_of("exit-code") > 0 {
+                    value_t!(m, "exit-code", i32).unwrap_or(0)
+                } else {
+                    defaults.exit_code.unwrap_or(0)
+                }
+            },
+            explain_exit_code: m.is_present("explain-exit-code"),
+            lockfile,
+            manifest,
+            workspace: m.is_present("workspace"),
+            keep_going: m.is_present("keep-going"),
+            resume: m.is_present("resume"),
+            all_members: m.is_present("all-members"),
+            no_aggregate: m.is_present("no-aggregate"),
+            jobs: value_t!(m, "jobs", usize).unwrap_or(1),
+            group_by_kind: m.value_of("group-by") == Some("kind"),
+            format,
+            cache: m.is_present("cache"),
+            suggest: m.is_present("suggest"),
+            registry_index: m.value_of("registry-index"),
+            registry_timeout: value_t!(m, "registry-timeout", u64).ok(),
+            assume_version: m.value_of("assume-version"),
+            sort: match m.value_of("sort") {
+                Some("severity") => SortOrder::Severity,
+                Some(_) => SortOrder::Name,
+                None => if m.is_present("suggest") {
+                    SortOrder::Severity
+                } else {
+                    SortOrder::Name
+                },
+            },
+            why: m.is_present("why"),
+            color: match m.value_of("color") {
+                Some("always") => true,
+                Some("never") => false,
+                _ => !m.is_present("no-color") && format == OutputFormat::Table &&
+                    !m.is_present("report-path") && !m.is_present("output-dir") &&
+                    util::stdout_is_tty(),
+            },
+            exclude_members: m.values_of("exclude-members")
+                .map(|v| v.collect())
+                .unwrap_or_default(),
+            changed_only: m.is_present("changed-only"),
+            only_explicit: m.is_present("only-explicit"),
+            compatible_latest: m.is_present("compatible-latest"),
+            summary_only: m.is_present("summary-only"),
+            json_compact_nulls: m.is_present("json-compact-nulls"),
+            interactive: m.is_present("interactive"),
+            checksums: m.is_present("checksums"),
+            show_kind: m.is_present("show-kind"),
+            collapse: m.is_present("collapse"),
+            concise_rm: m.is_present("concise-rm"),
+            links: m.is_present("links"),
+            unused: m.is_present("unused"),
+            internal_only: m.is_present("internal-only"),
+            ignore_internal: m.is_present("ignore-internal"),
+            ignore: match m.values_of("ignore") {
+                Some(names) => names.map(|n| n.to_owned()).collect(),
+                None => defaults.ignore.clone().unwrap_or_default(),
+            },
+            only: match m.value_of("only") {
+                Some("major") => Some(Severity::Major),
+                Some("minor") => Some(Severity::Minor),
+                Some("patch") => Some(Severity::Patch),
+                _ => None,
+            },
+            only_kind: match m.value_of("only-kind") {
+                Some("normal") => Some(DependencyKind::Normal),
+                Some("dev") => Some(DependencyKind::Development),
+                Some("build") => Some(DependencyKind::Build),
+                _ => None,
+            },
+            dependency_format: match m.value_of("dependency-format") {
+                Some("requirement") => DependencyFormat::Requirement,
+                Some("source") => DependencyFormat::Source,
+                _ => DependencyFormat::Bare,
+            },
+            duplicates: m.is_present("duplicates"),
+            graph_delta: m.is_present("graph-delta"),
+            report_path: m.value_of_os("report-path").map(PathBuf::from),
+            output_dir: m.value_of_os("output-dir").map(PathBuf::from),
+            formats: m.values_of("formats")
+                .map(|v| v.map(output_format).collect())
+                .unwrap_or_default(),
+            allowlist: m.value_of_os("allowlist").map(PathBuf::from),
+            badge: m.value_of_os("badge").map(PathBuf::from),
+            graph_dir: m.value_of_os("graph-dir").map(PathBuf::from),
+            temp_name,
+            cargo: m.value_of_os("cargo-path")
+                .map_or_else(|| PathBuf::from("cargo"), PathBuf::from),
+            against: match m.value_of_os("against") {
+                Some(path) => Some(util::file_in_dir(Path::new(path), "Cargo.toml")?),
+                None => None,
+            },
+            target: match m.value_of("target") {
+                Some(triple) => Some(Target::new(triple)?),
+                // Dependencies for the host platform are resolved by default, like cargo does
+                None => match Target::host() {
+                    Ok(host) => Some(host),
+                    Err(e) => {
+                        wlnerr!(
+                            "{} only checking the dependencies for every platform: {}",
+                            Format::Warning("warning:"),
+                            e.message()
+                        );
+                        None
+                    }
+                },
             },
-            manifest: try!(util::find_file(m.value_of("manifest-path").unwrap_or("Cargo.toml"), m.is_present("manifest-path"))),
-            lockfile: try!(util::find_file(m.value_of("lockfile-path").unwrap_or("Cargo.lock"), m.is_present("lockfile-path"))),
         };
         debugln!("Config:from_matches:cfg={:#?}", cfg);
         Ok(cfg)
@@ -5,6 +5,12 @@ use std::io;
 
 use fmt::Format;
 
+/// The exit code used when `cargo-outdated` itself fails, as opposed to finding new versions
+pub const EXIT_ERROR: i32 = 101;
+
+/// The exit code used when `--allowlist` is given and a package in `Cargo.lock` isn't approved
+pub const EXIT_UNAPPROVED: i32 = 102;
+
 /// Convenience type to return a result or a `CliError`
 pub type CliResult<T> = Result<T, CliError>;
 
@@ -13,6 +19,8 @@ pub type CliResult<T> = Result<T, CliError>;
 pub enum CliError {
     Generic(String),
     FileOpen(String),
+    /// A manifest which couldn't be deserialized
+    InvalidManifest(String),
     TomlTableRoot,
     NoRootDeps,
     NoNonRootDeps,
@@ -33,29 +41,32 @@ impl CliError {
     ///
     /// If the error is non-fatal then the error is printed to stdout and the
     /// exit status will be `0`. Otherwise, when the error is fatal, the error
-    /// is printed to stderr and the exit status will be `1`.
+    /// is printed to stderr and the exit status will be `EXIT_ERROR`.
     pub fn exit(&self) -> ! {
         if self.is_fatal() {
             wlnerr!("{}", self);
-            ::std::process::exit(1)
         } else {
             println!("{}", self);
-            ::std::process::exit(0)
         }
+        ::std::process::exit(self.exit_code())
     }
-}
 
-impl Display for CliError {
-    fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(f, "{} {}", Format::Error("error:"), self.description())
+    /// The exit status of the program when it stops with this error
+    pub fn exit_code(&self) -> i32 {
+        if self.is_fatal() {
+            EXIT_ERROR
+        } else {
+            0
+        }
     }
-}
 
-impl Error for CliError {
-    #[cfg_attr(feature = "lints", allow(match_same_arms))]
-    fn description(&self) -> &str {
+    /// The error without the `error:` prefix added by `Display`
+    pub fn message(&self) -> &str {
         match *self {
-            CliError::Generic(ref d) | CliError::FileOpen(ref d) | CliError::Io(ref d) => &*d,
+            CliError::Generic(ref d) |
+            CliError::FileOpen(ref d) |
+            CliError::InvalidManifest(ref d) |
+            CliError::Io(ref d) => d,
             CliError::TomlTableRoot => "couldn't find '[root]' table in Cargo.lock",
             CliError::NoRootDeps => "No root dependencies",
             CliError::NoNonRootDeps => "No non root dependencies",
@@ -64,10 +75,18 @@ impl Error for CliError {
             }
         }
     }
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{} {}", Format::Error("error:"), self.message())
+    }
+}
 
-    fn cause(&self) -> Option<&Error> { None }
+impl Error for CliError {
+    fn cause(&self) -> Option<&dyn Error> { None }
 }
 
 impl From<io::Error> for CliError {
-    fn from(e: io::Error) -> Self { CliError::Io(e.description().to_string()) }
+    fn from(e: io::Error) -> Self { CliError::Io(e.to_string()) }
 }
@@ -0,0 +1,84 @@
+//! Filters applied to the dependencies found before they're reported
+
+use cargo_files::{DependencyKind, DependencyStatus};
+use config::Config;
+use version::Severity;
+
+/// Removes or annotates dependencies before they're reported
+///
+/// Filters run after the results are cached, so the cache always holds every update found.
+pub trait ReportFilter {
+    fn apply(&self, statuses: Vec<DependencyStatus>) -> Vec<DependencyStatus>;
+}
+
+/// Keeps the dependencies whose newest update has exactly this severity, see `--only`
+pub struct SeverityFilter(pub Severity);
+
+impl ReportFilter for SeverityFilter {
+    fn apply(&self, mut statuses: Vec<DependencyStatus>) -> Vec<DependencyStatus> {
+        statuses.retain(|s| s.severity() == Some(self.0));
+        statuses
+    }
+}
+
+/// Keeps the dependencies of this kind, along with the ones they pull in, see `--only-kind`
+pub struct KindFilter(pub DependencyKind);
+
+impl ReportFilter for KindFilter {
+    fn apply(&self, mut statuses: Vec<DependencyStatus>) -> Vec<DependencyStatus> {
+        statuses.retain(|s| s.kind == self.0);
+        statuses
+    }
+}
+
+/// Keeps either the path dependencies, e.g. other workspace members, or the ones from a
+/// registry or git, see `--internal-only` and `--ignore-internal`
+pub struct SourceFilter {
+    pub internal: bool,
+}
+
+impl ReportFilter for SourceFilter {
+    fn apply(&self, mut statuses: Vec<DependencyStatus>) -> Vec<DependencyStatus> {
+        // Only path dependencies have no source in the lockfile
+        statuses.retain(|s| s.source.is_none() == self.internal);
+        statuses
+    }
+}
+
+/// Leaves out the dependencies with one of these names, see `--ignore`
+pub struct IgnoreFilter(pub Vec<String>);
+
+impl ReportFilter for IgnoreFilter {
+    fn apply(&self, mut statuses: Vec<DependencyStatus>) -> Vec<DependencyStatus> {
+        statuses.retain(|s| !self.0.contains(&s.name));
+        statuses
+    }
+}
+
+/// The filters enabled by the options in `cfg`, in the order they're applied
+pub fn from_config(cfg: &Config) -> Vec<Box<dyn ReportFilter>> {
+    let mut filters: Vec<Box<dyn ReportFilter>> = vec![];
+    if !cfg.ignore.is_empty() {
+        filters.push(Box::new(IgnoreFilter(cfg.ignore.clone())));
+    }
+    if let Some(only) = cfg.only {
+        filters.push(Box::new(SeverityFilter(only)));
+    }
+    if let Some(kind) = cfg.only_kind {
+        filters.push(Box::new(KindFilter(kind)));
+    }
+    if cfg.internal_only || cfg.ignore_internal {
+        filters.push(Box::new(SourceFilter {
+            internal: cfg.internal_only,
+        }));
+    }
+    filters
+}
+
+/// Runs each filter in turn over `statuses`
+pub fn apply_all(
+    filters: &[Box<dyn ReportFilter>],
+    statuses: Vec<DependencyStatus>,
+) -> Vec<DependencyStatus> {
+    filters.iter().fold(statuses, |statuses, f| f.apply(statuses))
+}
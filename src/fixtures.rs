@@ -0,0 +1,152 @@
+//! Temporary projects and a stand-in for `cargo`, shared by the tests of every module
+
+use std::fs::{self, File};
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+use tempdir::TempDir;
+
+/// The source of the packages written by `lockfile`
+pub const CRATES_IO: &str = "registry+https://github.com/rust-lang/crates.io-index";
+
+/// A temporary directory to write a project to, removed when it's dropped
+pub struct Project {
+    dir: TempDir,
+}
+
+impl Project {
+    pub fn new() -> Project {
+        Project {
+            dir: TempDir::new("cargo-outdated-test").unwrap(),
+        }
+    }
+
+    /// The absolute path of `path` in the project
+    pub fn path(&self, path: &str) -> PathBuf {
+        self.dir.path().join(path)
+    }
+
+    /// Writes `contents` to `path` in the project, creating the directories it's in
+    pub fn file(&self, path: &str, contents: &str) -> PathBuf {
+        let path = self.path(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        File::create(&path)
+            .unwrap()
+            .write_all(contents.as_bytes())
+            .unwrap();
+        path
+    }
+
+    /// Writes an executable shell script to `path` in the project
+    #[cfg(unix)]
+    pub fn script(&self, path: &str, body: &str) -> PathBuf {
+        let path = self.file(path, &format!("#!/bin/sh\n{}", body));
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    /// A `cargo` whose `update` writes the first of `lockfiles` whose pattern is found in the
+    /// manifest it's given, e.g. `"*"` for the manifest of the latest versions
+    ///
+    /// Every call is appended to `cargo.log` in the project, as the working directory followed
+    /// by the arguments.
+    #[cfg(unix)]
+    pub fn cargo_by(&self, lockfiles: &[(&str, &str)]) -> PathBuf {
+        let mut body = format!(
+            "echo \"$PWD $*\" >> '{}'\nmanifest=$3\n",
+            self.path("cargo.log").display()
+        );
+        for (i, &(pattern, lockfile)) in lockfiles.iter().enumerate() {
+            let path = self.file(&format!("fake-cargo/{}.lock", i), lockfile);
+            body.push_str(&format!(
+                "if grep -qF -e '{}' \"$manifest\"; then\n    \
+                 cp '{}' \"$(dirname \"$manifest\")/Cargo.lock\"\n    exit 0\nfi\n",
+                pattern,
+                path.display()
+            ));
+        }
+        body.push_str("echo 'no lockfile for this manifest' >&2\nexit 1\n");
+        self.script("fake-cargo/cargo", &body)
+    }
+
+    /// A `cargo` resolving to `compat` for the SemVer compatible versions and to `latest` for
+    /// the latest ones, see `cargo_by`
+    #[cfg(unix)]
+    pub fn cargo(&self, compat: &str, latest: &str) -> PathBuf {
+        self.cargo_by(&[("\"*\"", latest), ("", compat)])
+    }
+
+    /// The lines of `cargo.log`, see `cargo_by`
+    pub fn cargo_log(&self) -> Vec<String> {
+        fs::read_to_string(self.path("cargo.log"))
+            .unwrap_or_default()
+            .lines()
+            .map(|l| l.to_owned())
+            .collect()
+    }
+
+    /// The arguments checking the project's `Cargo.toml` and `Cargo.lock`, with its stand-in
+    /// `cargo` once one is written, followed by `more`
+    pub fn args(&self, more: &[&str]) -> Vec<String> {
+        let mut args = vec![
+            "-m".to_owned(),
+            self.path("Cargo.toml").display().to_string(),
+            "-l".to_owned(),
+            self.path("Cargo.lock").display().to_string(),
+        ];
+        let cargo = self.path("fake-cargo/cargo");
+        if cargo.exists() {
+            args.push("--cargo-path".to_owned());
+            args.push(cargo.display().to_string());
+        }
+        args.extend(more.iter().map(|a| a.to_string()));
+        args
+    }
+}
+
+/// The matches of `cargo outdated` with `args`
+pub fn matches<S: AsRef<str>>(args: &[S]) -> ArgMatches<'static> {
+    let args = ["cargo", "outdated"]
+        .iter()
+        .map(|a| a.to_string())
+        .chain(args.iter().map(|a| a.as_ref().to_owned()));
+    ::cli("test")
+        .get_matches_from_safe(args)
+        .unwrap()
+        .subcommand_matches("outdated")
+        .unwrap()
+        .clone()
+}
+
+/// An old style lockfile, with a `[root]` table, whose first package is the root and the others
+/// come from crates.io
+///
+/// Each package is `(name, version, dependencies)`, with the dependencies as `name version`.
+pub fn lockfile(packages: &[(&str, &str, &[&str])]) -> String {
+    workspace_lockfile(packages, &[])
+}
+
+/// Like `lockfile`, but the packages named in `members` are path dependencies, such as the
+/// members of a workspace
+pub fn workspace_lockfile(packages: &[(&str, &str, &[&str])], members: &[&str]) -> String {
+    let mut lockfile = String::new();
+    for (i, &(name, version, deps)) in packages.iter().enumerate() {
+        if i == 0 {
+            lockfile.push_str("[root]\n");
+        } else {
+            lockfile.push_str("\n[[package]]\n");
+        }
+        lockfile.push_str(&format!("name = \"{}\"\nversion = \"{}\"\n", name, version));
+        if i > 0 && !members.contains(&name) {
+            lockfile.push_str(&format!("source = \"{}\"\n", CRATES_IO));
+        }
+        if !deps.is_empty() {
+            let deps: Vec<_> = deps.iter().map(|d| format!("\"{}\"", d)).collect();
+            lockfile.push_str(&format!("dependencies = [{}]\n", deps.join(", ")));
+        }
+    }
+    lockfile
+}
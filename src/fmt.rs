@@ -1,10 +1,17 @@
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 #[cfg(all(feature = "color", not(target_os = "windows")))]
 use ansi_term::Colour::{Green, Red, Yellow};
 #[cfg(all(feature = "color", not(target_os = "windows")))]
 use ansi_term::ANSIString;
 
+// Inverted so that colors are on until the command line has been parsed
+static NO_COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Turns colored output on or off for every `Format` displayed from now on
+pub fn set_color(enabled: bool) { NO_COLOR.store(!enabled, Ordering::Relaxed); }
+
 #[allow(dead_code)]
 pub enum Format<T> {
     Error(T),
@@ -14,7 +21,7 @@ pub enum Format<T> {
 
 #[cfg(all(feature = "color", not(target_os = "windows")))]
 impl<T: AsRef<str>> Format<T> {
-    fn format(&self) -> ANSIString {
+    fn format(&self) -> ANSIString<'_> {
         match *self {
             Format::Error(ref e) => Red.bold().paint(e.as_ref()),
             Format::Warning(ref e) => Yellow.paint(e.as_ref()),
@@ -25,7 +32,17 @@ impl<T: AsRef<str>> Format<T> {
 
 #[cfg(all(feature = "color", not(target_os = "windows")))]
 impl<T: AsRef<str>> fmt::Display for Format<T> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{}", &self.format()) }
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if NO_COLOR.load(Ordering::Relaxed) {
+            match *self {
+                Format::Error(ref e) | Format::Warning(ref e) | Format::Good(ref e) => {
+                    write!(f, "{}", e.as_ref())
+                }
+            }
+        } else {
+            write!(f, "{}", &self.format())
+        }
+    }
 }
 
 #[cfg(any(not(feature = "color"), target_os = "windows"))]
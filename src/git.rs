@@ -0,0 +1,75 @@
+//! Comparing the manifest against the version committed to `git`
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+use std::process;
+
+use cargo_files::{self, Manifest};
+use error::{CliError, CliResult};
+
+/// The package names of the dependencies which were added or changed in `manifest` since `HEAD`
+///
+/// Dependencies are matched by the package they refer to, so renaming one with `package = "..."`
+/// doesn't hide it.
+pub fn changed_dependencies(manifest: &Path) -> CliResult<HashSet<String>> {
+    let current = Manifest::from_manifest_path(manifest)?;
+    let committed = Manifest::from_contents(
+        &committed_contents(manifest)?,
+        &format!("{} at HEAD", manifest.display())
+    )?;
+
+    let mut changed = HashSet::new();
+    for ((_, curr_deps), (_, head_deps)) in current
+        .dependency_tables()
+        .into_iter()
+        .zip(committed.dependency_tables())
+    {
+        let head_deps: HashMap<_, _> = head_deps
+            .iter()
+            .map(|(dep_name, dep_pac)| (cargo_files::package_name(dep_name, dep_pac), dep_pac))
+            .collect();
+        for (dep_name, dep_pac) in curr_deps {
+            let name = cargo_files::package_name(dep_name, dep_pac);
+            if head_deps.get(name) != Some(&dep_pac) {
+                changed.insert(name.to_owned());
+            }
+        }
+    }
+    Ok(changed)
+}
+
+/// The `HEAD` commit of the repository containing `manifest`
+pub fn head_commit(manifest: &Path) -> CliResult<String> {
+    let output = git(manifest, &[OsStr::new("rev-parse"), OsStr::new("HEAD")])?;
+    Ok(output.trim().to_owned())
+}
+
+/// The contents of `path` as of the `HEAD` commit
+fn committed_contents(path: &Path) -> CliResult<String> {
+    // The path may not be UTF-8, so the object name is built up as an `OsString`
+    let mut object = OsString::from("HEAD:./");
+    object.push(path.file_name().unwrap_or_else(|| OsStr::new("Cargo.toml")));
+    git(path, &[OsStr::new("show"), &object]).map_err(|e| {
+        CliError::Generic(format!(
+            "Failed to read {} from the HEAD commit: {}",
+            path.display(),
+            e.message()
+        ))
+    })
+}
+
+/// The output of `git` with `args`, run in the directory containing `path`
+fn git(path: &Path, args: &[&OsStr]) -> CliResult<String> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let output = process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()?;
+    if !output.status.success() {
+        return Err(CliError::Generic(
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|e| CliError::Generic(e.to_string()))
+}
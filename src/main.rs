@@ -56,20 +56,95 @@
 //!     cargo outdated [FLAGS] [OPTIONS]
 //!
 //! FLAGS:
+//!         --all-members       Check every workspace member, not only the default-members
+//!         --cache             Reuse the results of the last run if the manifest and lockfile are unchanged
+//!         --changed-only      Only check direct dependencies added or changed in Cargo.toml since the last git commit
+//!         --checksums         Show the lockfile checksum of each project version
+//!         --collapse          Show the latest version as -- when it's the same as the SemVer compatible one
+//!         --compatible-latest Limit the latest versions to those SemVer compatible with the lower bound of each requirement, even where the requirement itself is narrower
+//!         --concise-rm        List the dependencies removed in the latest versions on one line instead of in the table
+//!         --duplicates        Warn about packages which the latest versions would need more than one version of
+//!         --explain-exit-code Print why the exit code is not 0 to stderr
+//!         --fail-fast         Stop at the first workspace member that cannot be checked (default)
+//!         --graph-delta       Print how many packages the latest versions would add to and remove from Cargo.lock
 //!     -h, --help              Prints help information
+//!         --no-color          Disable coloring (Equivalent to --color=never)
+//!         --no-exit-code      Exit with 0 when newer versions are found, whatever --exit-code or .outdated.toml say
+//!         --ignore-internal   Only report dependencies from a registry or git, not path dependencies such as workspace members
+//!         --internal-only     Only report path dependencies, such as workspace members
+//!     -i, --interactive       Ask which SemVer compatible updates of direct dependencies to apply to Cargo.lock
+//!         --json-compact-nulls  Leave the keys whose value is null out of the JSON report
+//!         --keep-going        Keep checking the remaining workspace members after one fails
+//!         --links             Add a column with the repository, or else homepage, of each registry dependency
+//!         --no-aggregate      Print a separate report for each workspace member, even when they share dependencies
+//!         --only-explicit     Only report the dependencies declared in Cargo.toml, never the ones they pull in
+//!         --resume            Save the results of each workspace member as it is checked, and skip the members saved by an interrupted run
 //!     -R, --root-deps-only    Only check root dependencies (Equivalent to --depth=1)
+//!         --show-kind         Add a column with the kind of each dependency (normal, dev or build)
+//!         --summary-only      Only print the number of dependencies with newer versions
+//!         --suggest           Print the commands which take the updates of direct dependencies
+//!         --unused            List declared dependencies which look unused
 //!     -V, --version           Prints version information
 //!     -v, --verbose           Print verbose output
+//!         --why               Print the shortest chain of packages to each transitive dependency
+//!     -w, --workspace         Check every member of the workspace
 //!
 //! OPTIONS:
+//!         --against <PATH>          Compare the requirements of the dependencies with those in another Cargo.toml, or the directory containing it
+//!         --allowlist <FILE>        Fail unless every package in Cargo.lock has a version approved by FILE
+//!         --assume-version <X.Y.Z>  Resolve as if the checked package had this version, e.g. to prepare a release
+//!         --badge <FILE>            Write a shields.io endpoint badge with the number of outdated dependencies to FILE
+//!         --cargo-path <PATH>       The cargo binary which resolves the dependencies (Defaults to cargo on the PATH)
+//!         --color <WHEN>            Coloring (Defaults to auto) [values: auto, always, never]
 //!     -d, --depth <NUM>             How deep in the dependency chain to search (Defaults to all dependencies when omitted)
+//!         --dependency-format <STYLE>   How versions are shown [default: bare] [values: bare, requirement, source]
+//!         --exclude-members <NAME>...   Workspace members to skip
 //!         --exit-code <NUM>         The exit code to return on new versions found [default: 0]
+//!         --format <FORMAT>         Output format [default: table] [values: table, plain, html, sarif, json, markdown]
+//!         --formats <FORMAT>...     The formats written to --output-dir [values: table, plain, html, sarif, json, markdown]
+//!         --graph-dir <DIR>         Write the current, SemVer compatible and latest dependency graphs to DIR in Graphviz DOT format
+//!         --group-by <GROUP>        Split the output into a table per group [values: kind]
+//!         --ignore <NAME>...        Dependencies to leave out of the report
+//!     -j, --jobs <N>                Number of workspace members to check at once (Defaults to 1)
 //!     -l, --lockfile-path <PATH>    An absolute path to the Cargo.lock to use (Defaults to Cargo.lock in project root)
-//!     -m, --manifest-path <PATH>    An absolute path to the Cargo.toml to use (Defaults to Cargo.toml in project root)
+//!     -m, --manifest-path <PATH>    An absolute path to the Cargo.toml file, or the directory containing it, to use, or a glob matching several projects (Defaults to Cargo.toml in project root)
+//!         --only <SEVERITY>         Only show dependencies whose newest update is of this severity (Defaults to any) [values: major, minor, patch, any]
+//!         --only-kind <KIND>        Only show dependencies of this kind, and the ones they pull in [values: normal, dev, build]
+//!         --output-dir <DIR>        Also write the report to a file in DIR for each of --formats
 //!     -p, --package <PKG>...        Package to inspect for updates
+//!         --registry-index <URL>    The registry index to check for the latest versions (Defaults to crates.io)
+//!         --registry-timeout <SECONDS>  How long cargo waits on the registry before giving up (Defaults to the http.timeout of cargo)
+//!         --report-path <FILE>      Write the report to FILE instead of stdout
+//!         --sort <ORDER>            How to order the dependencies (Defaults to name, or to severity with --suggest) [values: name, severity]
 //!     -r, --root <ROOT>             Package to treat as the root package
+//!         --target <TRIPLE>         Also check the dependencies declared for this target platform (Defaults to the host)
+//!         --temp-name <NAME>        Write the temporary project to NAME in the system temporary directory and keep it, suffixed with the member name with --workspace or the project directory with a glob, and reused by later runs (Defaults to a random name, removed after the run)
 //! ```
 //!
+//! ### Project Defaults
+//!
+//! A `.outdated.toml` next to the project's `Cargo.toml` can set defaults for `format`, `depth`,
+//! `exit-code` and `ignore`. Options given on the command line take precedence.
+//!
+//! ```ignore
+//! format = "plain"
+//! depth = 1
+//! exit-code = 2
+//! ignore = ["winapi"]
+//! ```
+//!
+//! ### Exit Codes
+//!
+//! * `0` when all dependencies are up to date
+//! * the value of `--exit-code` (`0` by default, and always with `--no-exit-code`) when newer
+//!   versions were found. `--explain-exit-code` then prints how many, and of which severity, to
+//!   stderr.
+//! * `101` when `cargo-outdated` itself failed, e.g. an option was invalid, a manifest couldn't
+//!   be read or `cargo update` failed
+//! * `102` when `--allowlist` is given and a package in `Cargo.lock` isn't approved
+//!
+//! `--exit-code` can't be set to `101` or `102`, so the three cases can always be told apart.
+//!
 //! ## License
 //!
 //! `cargo-outdated` is released under the terms of the MIT license. See the LICENSE-MIT file for the details.
@@ -80,6 +155,10 @@ extern crate toml;
 extern crate tempdir;
 #[cfg(feature = "color")]
 extern crate ansi_term;
+#[cfg(feature = "color")]
+extern crate atty;
+extern crate crossbeam;
+extern crate glob;
 extern crate tabwriter;
 extern crate serde;
 #[macro_use]
@@ -87,32 +166,76 @@ extern crate serde_derive;
 
 #[macro_use]
 mod macros;
+mod allowlist;
+mod cache;
 mod config;
 mod error;
+mod filter;
 mod fmt;
+mod git;
+mod report;
+mod target;
+#[cfg(test)]
+mod fixtures;
 mod util;
+mod version;
 mod cargo_files;
 mod cargo_ops;
 
-use std::io::{Write, stdout};
-use std::path::Path;
-#[cfg(feature="debug")]
+use std::fs::{self, File};
+use std::io::{BufRead, Write, stdin, stdout};
+use std::ffi::{OsStr, OsString};
+use std::path::{Component, Path, PathBuf};
+use std::cmp;
 use std::env;
 use std::process;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use clap::{App, AppSettings, Arg, SubCommand, ArgMatches};
-use tabwriter::TabWriter;
+use clap::{App, AppSettings, Arg, ErrorKind, SubCommand, ArgMatches};
 
-use config::Config;
+use cargo_files::{DependencyKind, DependencyStatus, Update};
+use config::{Config, OutputFormat};
 use error::{CliResult, CliError};
 use fmt::Format;
 
 fn main() {
-    debugln!("main:args={:?}", env::args().collect::<Vec<_>>());
-    let m = App::new("cargo-outdated")
+    debugln!("main:args={:?}", env::args_os().collect::<Vec<_>>());
+    // Only ask cargo for its version when it's going to be printed
+    let version = if env::args_os().any(|a| a == "-V" || a == "--version") {
+        version_with_cargo(&cargo_path_arg())
+    } else {
+        concat!("v", crate_version!()).to_owned()
+    };
+    let m = cli(&version)
+        .get_matches_safe()
+        .unwrap_or_else(|e| match e.kind {
+            ErrorKind::HelpDisplayed | ErrorKind::VersionDisplayed => e.exit(),
+            // A mistyped option mustn't exit with the code given to --exit-code
+            _ => {
+                wlnerr!("{}", e.message);
+                process::exit(error::EXIT_ERROR)
+            }
+        });
+
+    if let Some(m) = m.subcommand_matches("outdated") {
+        match execute(m) {
+            Ok(code) => {
+                debugln!("main:exit_code={}", code);
+                process::exit(code)
+            }
+            Err(e) => e.exit(),
+        }
+    }
+}
+
+/// The command line of `cargo outdated`, giving `version` for `-V`
+fn cli<'a, 'b>(version: &'b str) -> App<'a, 'b> {
+    App::new("cargo-outdated")
         .author("Kevin K. <kbknapp@gmail.com>")
         .about("Displays information about project dependency versions")
-        .version(concat!("v", crate_version!()))
+        .version(version)
         // We have to lie about our binary name since this will be a third party
         // subcommand for cargo
         .bin_name("cargo")
@@ -132,42 +255,675 @@ fn main() {
                  -d, --depth [NUM]          'How deep in the dependency chain to search \
                                             (Defaults to all dependencies when omitted)'")
             .args(&[
+                Arg::from_usage("--dependency-format [STYLE] 'How versions are shown'")
+                    .possible_values(&["bare", "requirement", "source"])
+                    .default_value("bare"),
+                Arg::from_usage("--duplicates 'Warn about packages which the latest versions would need \
+                                 more than one version of'"),
                 Arg::from_usage("--exit-code [NUM]     'The exit code to return on new versions found'")
-                    .default_value("0"),
+                    .default_value("0")
+                    .validator(is_exit_code),
                 Arg::from_usage(
                     "-R, --root-deps-only  'Only check root dependencies (Equivalent to --depth=1)'")
                     .conflicts_with("depth"),
-                Arg::from_usage("-m, --manifest-path [PATH] 'An absolute path to the Cargo.toml file to use \
+                Arg::from_usage("-m, --manifest-path [PATH] 'An absolute path to the Cargo.toml file, or the \
+                                                             directory containing it, to use, or a glob \
+                                                             matching several projects \
                                                              (Defaults to Cargo.toml in project root)'")
-                    .validator(is_file),
+                    .validator_os(is_file_or_dir),
                 Arg::from_usage("-l, --lockfile-path [PATH] 'An absolute path to the Cargo.lock to use \
                                                              (Defaults to Cargo.lock in project root)'")
-                    .validator(is_file)]))
-        .get_matches();
+                    .validator_os(is_file),
+                Arg::from_usage("--all-members 'Check every workspace member, not only the default-members'")
+                    .requires("workspace"),
+                Arg::from_usage("--allowlist [FILE] 'Fail unless every package in Cargo.lock has a \
+                                 version approved by FILE'"),
+                Arg::from_usage("--cache 'Reuse the results of the last run if the manifest and lockfile \
+                                 are unchanged'"),
+                Arg::from_usage("--changed-only 'Only check direct dependencies added or changed in \
+                                 Cargo.toml since the last git commit'"),
+                Arg::from_usage("--checksums 'Show the lockfile checksum of each project version'"),
+                Arg::from_usage("--color [WHEN] 'Coloring (Defaults to auto)'")
+                    .possible_values(&["auto", "always", "never"]),
+                Arg::from_usage("--no-color 'Disable coloring (Equivalent to --color=never)'")
+                    .conflicts_with("color"),
+                Arg::from_usage("--no-exit-code 'Exit with 0 when newer versions are found, whatever \
+                                 --exit-code or .outdated.toml say'"),
+                Arg::from_usage("--explain-exit-code 'Print why the exit code is not 0 to stderr'"),
+                Arg::from_usage("--format [FORMAT] 'Output format'")
+                    .possible_values(config::OUTPUT_FORMATS)
+                    .default_value("table"),
+                Arg::from_usage("--graph-dir [DIR] 'Write the current, SemVer compatible and latest \
+                                 dependency graphs to DIR in Graphviz DOT format'"),
+                Arg::from_usage("--graph-delta 'Print how many packages the latest versions would add to \
+                                 and remove from Cargo.lock'"),
+                Arg::from_usage("--group-by [GROUP] 'Split the output into a table per group'")
+                    .possible_values(&["kind"]),
+                Arg::from_usage("--only [SEVERITY] 'Only show dependencies whose newest update is of this \
+                                 severity (Defaults to any)'")
+                    .possible_values(&["major", "minor", "patch", "any"]),
+                Arg::from_usage("--only-kind [KIND] 'Only show dependencies of this kind, and the ones \
+                                 they pull in'")
+                    .possible_values(&["normal", "dev", "build"]),
+                Arg::from_usage("--registry-index [URL] 'The registry index to check for the latest \
+                                 versions (Defaults to crates.io)'")
+                    .validator(is_registry_url),
+                Arg::from_usage("--registry-timeout [SECONDS] 'How long cargo waits on the registry \
+                                 before giving up (Defaults to the http.timeout of cargo)'")
+                    .validator(is_timeout),
+                Arg::from_usage("--against [PATH] 'Compare the requirements of the dependencies with those \
+                                 in another Cargo.toml, or the directory containing it'")
+                    .validator_os(is_file_or_dir)
+                    .conflicts_with("workspace"),
+                Arg::from_usage("--badge [FILE] 'Write a shields.io endpoint badge with the number of \
+                                 outdated dependencies to FILE'"),
+                Arg::from_usage("--cargo-path [PATH] 'The cargo binary which resolves the dependencies \
+                                 (Defaults to cargo on the PATH)'")
+                    .validator_os(is_executable),
+                Arg::from_usage("--assume-version [X.Y.Z] 'Resolve as if the checked package had this \
+                                 version, e.g. to prepare a release'")
+                    .validator(is_version),
+                Arg::from_usage("--output-dir [DIR] 'Also write the report to a file in DIR for each of \
+                                 --formats'")
+                    .requires("formats"),
+                Arg::from_usage("--formats [FORMAT]... 'The formats written to --output-dir'")
+                    .possible_values(config::OUTPUT_FORMATS)
+                    .use_delimiter(true)
+                    .requires("output-dir"),
+                Arg::from_usage("--report-path [FILE] 'Write the report to FILE instead of stdout'"),
+                Arg::from_usage("--show-kind 'Add a column with the kind of each dependency (normal, dev \
+                                 or build)'"),
+                Arg::from_usage("--sort [ORDER] 'How to order the dependencies (Defaults to name, or to \
+                                 severity with --suggest)'")
+                    .possible_values(&["name", "severity"]),
+                Arg::from_usage("--summary-only 'Only print the number of dependencies with newer versions'"),
+                Arg::from_usage("--json-compact-nulls 'Leave the keys whose value is null out of the JSON \
+                                 report'"),
+                Arg::from_usage("--suggest 'Print the commands which take the updates of direct dependencies'"),
+                Arg::from_usage("--unused 'List declared dependencies which look unused'"),
+                Arg::from_usage("--why 'Print the shortest chain of packages to each transitive dependency'"),
+                Arg::from_usage("--target [TRIPLE] 'Also check the dependencies declared for this target \
+                                 platform (Defaults to the host)'"),
+                Arg::from_usage("--temp-name [NAME] 'Write the temporary project to NAME in the system \
+                                 temporary directory and keep it, suffixed with the member name with \
+                                 --workspace or the project directory with a glob, and reused by later \
+                                 runs (Defaults to a random name, removed after the run)'")
+                    .validator_os(is_temp_name),
+                Arg::from_usage("-w, --workspace 'Check every member of the workspace'"),
+                Arg::from_usage("--collapse 'Show the latest version as -- when it is the same as the \
+                                 SemVer compatible one'"),
+                Arg::from_usage("--compatible-latest 'Limit the latest versions to those SemVer compatible \
+                                 with the lower bound of each requirement, even where the \
+                                 requirement itself is narrower'"),
+                Arg::from_usage("--concise-rm 'List the dependencies removed in the latest versions on one \
+                                 line instead of in the table'"),
+                Arg::from_usage("--exclude-members [NAME]... 'Workspace members to skip'")
+                    .requires("workspace"),
+                Arg::from_usage("--ignore [NAME]... 'Dependencies to leave out of the report'"),
+                Arg::from_usage("--fail-fast 'Stop at the first workspace member that cannot be checked (default)'")
+                    .requires("workspace")
+                    .conflicts_with("keep-going"),
+                Arg::from_usage("-j, --jobs [N] 'Number of workspace members to check at once (Defaults to 1)'")
+                    .requires("workspace")
+                    .validator(is_jobs),
+                Arg::from_usage("--ignore-internal 'Only report dependencies from a registry or git, not \
+                                 path dependencies such as workspace members'")
+                    .conflicts_with("internal-only"),
+                Arg::from_usage("--internal-only 'Only report path dependencies, such as workspace \
+                                 members'"),
+                Arg::from_usage("--links 'Add a column with the repository, or else homepage, of each \
+                                 registry dependency'"),
+                Arg::from_usage("--only-explicit 'Only report the dependencies declared in Cargo.toml, never \
+                                 the ones they pull in'"),
+                Arg::from_usage("-i, --interactive 'Ask which SemVer compatible updates of direct \
+                                 dependencies to apply to Cargo.lock'"),
+                Arg::from_usage("--resume 'Save the results of each workspace member as it is checked, and \
+                                 skip the members saved by an interrupted run'")
+                    .requires("workspace"),
+                Arg::from_usage("--keep-going 'Keep checking the remaining workspace members after one fails'")
+                    .requires("workspace"),
+                Arg::from_usage("--no-aggregate 'Print a separate report for each workspace member, even \
+                                 when they share dependencies'")
+                    .requires("workspace")]))
+}
 
-    if let Some(m) = m.subcommand_matches("outdated") {
-        match execute(m) {
-            Ok(code) => {
-                debugln!("main:exit_code={}", code);
-                process::exit(code)
+fn execute(m: &ArgMatches) -> CliResult<i32> {
+    debugln!("execute:m={:#?}", m);
+    // Paths which aren't valid UTF-8 can't be globs, as the glob crate only takes strings
+    let pattern = match m.value_of_os("manifest-path").and_then(|path| path.to_str()) {
+        Some(path) if util::is_glob(path) => path,
+        _ => {
+            let cfg = Config::from_matches(m)?;
+            fmt::set_color(cfg.color);
+            let mut out = report_writer(cfg.report_path.as_ref())?;
+            return check(&cfg, &mut *out);
+        }
+    };
+
+    // Unrelated projects matched by a glob are checked one after the other, into the same report
+    let manifests = util::glob_manifests(pattern)?;
+    if manifests.len() > 1 {
+        // Each project would overwrite the files of the one before
+        for arg in &["badge", "output-dir"] {
+            if m.is_present(arg) {
+                return Err(CliError::Generic(format!(
+                    "--{} can't be used when --manifest-path matches several projects",
+                    arg
+                )));
             }
-            Err(e) => e.exit(),
         }
     }
+    let mut out = report_writer(m.value_of_os("report-path").map(Path::new))?;
+    let mut code = 0;
+    for manifest in manifests {
+        let cfg = Config::for_manifest(m, Some(manifest))?;
+        fmt::set_color(cfg.color);
+        writeln!(out, "{}\n", Format::Good(cfg.manifest.to_string_lossy()))?;
+        code = cmp::max(code, check(&cfg, &mut *out)?);
+        writeln!(out)?;
+    }
+    Ok(code)
 }
 
-fn execute(m: &ArgMatches) -> CliResult<i32> {
-    debugln!("execute:m={:#?}", m);
-    let cfg = try!(Config::from_matches(m));
+/// Where the report is written, `path` or else stdout
+fn report_writer<P: AsRef<Path>>(path: Option<P>) -> CliResult<Box<dyn Write>> {
+    match path {
+        Some(path) => {
+            let path = path.as_ref();
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            Ok(Box::new(File::create(path)?))
+        }
+        None => Ok(Box::new(stdout())),
+    }
+}
+
+/// Checks the project, or workspace, of `cfg.manifest` and writes the results to `out`
+fn check(cfg: &Config, out: &mut dyn Write) -> CliResult<i32> {
+    let cache_key = if cfg.cache {
+        Some(cache::key(cfg)?)
+    } else {
+        None
+    };
+    let mut failures = vec![];
+    let mut skipped = vec![];
+    let statuses = match cache_key.and_then(|key| cache::load(cfg, key)) {
+        Some(statuses) => {
+            verboseln!(cfg, "{}", Format::Good("Using cached results"));
+            statuses
+        }
+        None => {
+            let statuses = if cfg.workspace {
+                check_workspace(cfg, &mut failures, &mut skipped)?
+            } else {
+                let manifest = project_manifest(cfg)?;
+                check_project(cfg, &manifest, &cfg.lockfile, cfg.root)?
+            };
+            if let Some(key) = cache_key {
+                // A partial workspace run shouldn't hide the failed or skipped members next time
+                if failures.is_empty() && skipped.is_empty() {
+                    cache::store(cfg, key, &statuses)?;
+                }
+            }
+            statuses
+        }
+    };
+    let statuses = filter::apply_all(&filter::from_config(cfg), statuses);
+    // Workspace members may each have their own edition, so it's only shown for a single project
+    let edition = if cfg.workspace {
+        None
+    } else {
+        let manifest_path = project_manifest(cfg)?;
+        cargo_files::Manifest::from_manifest_path(&manifest_path)?.edition(&manifest_path)
+    };
+
+    // A single HTML page, SARIF log or JSON object can't hold several reports, so they're always
+    // aggregated
+    let sections = match cfg.format {
+        OutputFormat::Table | OutputFormat::Plain | OutputFormat::Markdown => true,
+        OutputFormat::Html | OutputFormat::Sarif | OutputFormat::Json => false,
+    };
+    if cfg.workspace && cfg.no_aggregate && sections {
+        // Members which were skipped or failed have no results to show
+        let reported = checked_members(cfg)?.into_iter().filter(|(member, _)| {
+            !skipped.contains(member) && !failures.iter().any(|(m, _)| m == member)
+        });
+        for (_, name) in reported {
+            let section: Vec<_> = statuses
+                .iter()
+                .filter(|s| s.path.first() == Some(&name))
+                .cloned()
+                .collect();
+            if cfg.format == OutputFormat::Markdown {
+                writeln!(out, "## {}\n", name)?;
+            } else {
+                writeln!(out, "{}\n", Format::Warning(&name))?;
+            }
+            report::print_statuses(out, &section, true, cfg)?;
+            writeln!(out)?;
+        }
+    } else {
+        if let Some(ref edition) = edition {
+            if cfg.format == OutputFormat::Table && !cfg.summary_only {
+                writeln!(out, "Edition {}\n", edition)?;
+            }
+        }
+        report::print_statuses(out, &statuses, failures.is_empty(), cfg)?;
+    }
+    if let Some(ref edition) = edition {
+        warn_newer_editions(edition, &statuses);
+    }
+    if let Some(ref badge) = cfg.badge {
+        report::write_badge(badge, &statuses)?;
+    }
+    if let Some(ref dir) = cfg.output_dir {
+        for path in report::write_reports(dir, &statuses, failures.is_empty(), cfg)? {
+            verboseln!(cfg, "Wrote {}", Format::Good(path.to_string_lossy()));
+        }
+    }
+    if cfg.why {
+        report::print_paths(out, &statuses)?;
+    }
+    if cfg.suggest {
+        report::print_suggestions(out, &statuses)?;
+    }
+    if cfg.unused {
+        report::print_unused(out, &unused_dependencies(cfg)?)?;
+    }
+    if let Some(ref against) = cfg.against {
+        report::print_divergences(out, against, &divergences(cfg, against)?)?;
+    }
+    if cfg.interactive {
+        apply_interactively(&statuses, cfg, &mut stdin().lock(), &mut stdout())?;
+    }
+
+    if let Some(ref allowlist) = cfg.allowlist {
+        let unapproved = allowlist::unapproved(allowlist, &cfg.lockfile)?;
+        if !unapproved.is_empty() {
+            for (name, version) in &unapproved {
+                wlnerr!("{} {} {} is not on the allowlist", Format::Error("error:"), name, version);
+            }
+            wlnerr!(
+                "{} {} package(s) in {} are not on the allowlist {}",
+                Format::Error("error:"),
+                unapproved.len(),
+                cfg.lockfile.display(),
+                allowlist.display()
+            );
+            return Ok(error::EXIT_UNAPPROVED);
+        }
+    }
+
+    if !failures.is_empty() {
+        for (member, e) in &failures {
+            wlnerr!("{} member '{}': {}", Format::Error("error:"), member, e.message());
+        }
+        return Err(CliError::Generic(format!(
+            "{} workspace member(s) could not be checked",
+            failures.len()
+        )));
+    }
+
+    let code = if statuses.is_empty() { 0 } else { cfg.exit_code };
+    if cfg.explain_exit_code && code != 0 {
+        wlnerr!("{}", report::exit_reason(code, &statuses));
+    }
+    Ok(code)
+}
 
+/// Warns about the latest versions which need a newer edition than the project's `edition`
+///
+/// Only the packages cargo has already unpacked can be checked, nothing is downloaded.
+fn warn_newer_editions(edition: &str, statuses: &[DependencyStatus]) {
+    let year = |edition: &str| edition.parse::<u32>().unwrap_or(0);
+    let mut seen = HashSet::new();
+    for status in statuses {
+        let latest = match status.latest {
+            Update::Version(ref latest) => latest,
+            _ => continue,
+        };
+        if !seen.insert((&status.name, latest)) {
+            continue;
+        }
+        if let Some(needed) = util::package_edition(&status.name, latest) {
+            if year(&needed) > year(edition) {
+                wlnerr!(
+                    "{} {} {} needs edition {}, the project uses {}",
+                    Format::Warning("warning:"),
+                    status.name,
+                    latest,
+                    needed,
+                    edition
+                );
+            }
+        }
+    }
+}
+
+/// Asks whether to take each SemVer compatible update of a direct dependency and applies the
+/// chosen ones to the project's lockfile
+///
+/// The questions are written to `output` and answered by a line each from `input`. Only
+/// compatible updates are offered, since anything newer needs the manifest edited by hand.
+fn apply_interactively(
+    statuses: &[DependencyStatus],
+    cfg: &Config,
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+) -> CliResult<()> {
+    let mut updates: Vec<_> = statuses
+        .iter()
+        .filter(|s| s.parent.is_none())
+        .filter_map(|s| match s.compat {
+            Update::Version(ref v) => Some((&*s.name, &*s.project, &**v)),
+            _ => None,
+        })
+        .collect();
+    updates.sort();
+    updates.dedup();
+
+    for (name, from, to) in updates {
+        write!(output, "Update {} from {} to {}? [y/N] ", name, from, to)?;
+        output.flush()?;
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            break;
+        }
+        let answer = answer.trim().to_lowercase();
+        if answer == "y" || answer == "yes" {
+            cargo_ops::cargo_update_precise(
+                &cfg.cargo,
+                cfg.registry_timeout,
+                &cfg.manifest,
+                name,
+                from,
+                to
+            )?;
+            writeln!(output, "{} {} to {}", Format::Good("Updated"), name, to)?;
+        }
+    }
+    Ok(())
+}
+
+/// Finds the manifest of the package to check when not using `--workspace`
+///
+/// A virtual manifest has no package of its own, so `--root` must name one of its members.
+fn project_manifest(cfg: &Config) -> CliResult<PathBuf> {
+    let ws_manifest = cargo_files::WorkspaceManifest::from_manifest_path(&cfg.manifest)?;
+    if ws_manifest.package.is_some() {
+        return Ok(cfg.manifest.clone());
+    }
+    let root = match cfg.root {
+        Some(r) => r,
+        None => {
+            return Err(CliError::Generic(format!(
+                "{} is a virtual manifest, use --workspace or --root <MEMBER>",
+                cfg.manifest.display()
+            )))
+        }
+    };
+    match ws_manifest.find_member(&cfg.manifest, root)? {
+        Some(manifest) => Ok(manifest),
+        None => Err(CliError::Generic(format!(
+            "'{}' is not a member of the workspace {}",
+            root,
+            cfg.manifest.display()
+        ))),
+    }
+}
+
+/// The declared dependencies of the project, or of each workspace member, which look unused
+///
+/// This only compares the manifest with the lockfile and `[features]`, so it can't tell whether a
+/// dependency is actually used by the code.
+fn unused_dependencies(
+    cfg: &Config,
+) -> CliResult<Vec<(String, DependencyKind, String, &'static str)>> {
+    let manifests = if cfg.workspace {
+        let ws_manifest = cargo_files::WorkspaceManifest::from_manifest_path(&cfg.manifest)?;
+        let members = if cfg.all_members {
+            ws_manifest.members(&cfg.manifest)
+        } else {
+            ws_manifest.default_members(&cfg.manifest)
+        };
+        members
+            .into_iter()
+            .filter(|member| !cfg.exclude_members.contains(&&**member))
+            .map(|member| {
+                cargo_files::WorkspaceManifest::member_manifest_path(&cfg.manifest, &member)
+            })
+            .collect()
+    } else {
+        vec![project_manifest(cfg)?]
+    };
+
+    let mut unused = vec![];
+    for manifest in manifests {
+        let manifest = match cargo_files::Manifest::from_manifest_path(&manifest) {
+            Ok(manifest) => manifest,
+            // Skipped with a warning when the workspace was checked
+            Err(CliError::InvalidManifest(_)) if cfg.workspace => continue,
+            Err(e) => return Err(e),
+        };
+        let name = match manifest.name() {
+            Some(name) => name.to_owned(),
+            None => continue,
+        };
+        if cfg.exclude_members.contains(&&*name) {
+            continue;
+        }
+        let resolved = parse_tree(&cfg.lockfile, Some(&name), 1)?.direct_dependencies();
+        for (kind, dep_name, reason) in manifest.unused_dependencies(&resolved) {
+            unused.push((name.clone(), kind, dep_name, reason));
+        }
+    }
+    unused.sort();
+    Ok(unused)
+}
+
+/// The members checked by `--workspace` which aren't excluded, along with their package names
+fn checked_members(cfg: &Config) -> CliResult<Vec<(String, String)>> {
+    let ws_manifest = cargo_files::WorkspaceManifest::from_manifest_path(&cfg.manifest)?;
+    let members = if cfg.all_members {
+        ws_manifest.members(&cfg.manifest)
+    } else {
+        ws_manifest.default_members(&cfg.manifest)
+    };
+    let mut checked = vec![];
+    for member in members {
+        let manifest = cargo_files::WorkspaceManifest::member_manifest_path(&cfg.manifest, &member);
+        let name = cargo_files::WorkspaceManifest::from_manifest_path(&manifest)
+            .ok()
+            .and_then(|m| m.name().map(|n| n.to_owned()))
+            .unwrap_or_else(|| member.clone());
+        if !cfg.exclude_members.contains(&&*name) && !cfg.exclude_members.contains(&&*member) {
+            checked.push((member, name));
+        }
+    }
+    Ok(checked)
+}
+
+/// The dependencies of the project which have a different requirement in `against`, as
+/// `(name, requirement, other requirement)`
+///
+/// Dependencies only declared in one of the manifests aren't compared.
+fn divergences(cfg: &Config, against: &Path) -> CliResult<Vec<(String, String, String)>> {
+    let ours = cargo_files::Manifest::from_manifest_path(project_manifest(cfg)?)?
+        .requirements();
+    let theirs = cargo_files::Manifest::from_manifest_path(against)?.requirements();
+    let mut divergences: Vec<_> = ours.into_iter()
+        .filter_map(|(name, req)| match theirs.get(&name) {
+            Some(other) if *other != req => Some((name, req, other.clone())),
+            _ => None,
+        })
+        .collect();
+    divergences.sort();
+    Ok(divergences)
+}
+
+/// Checks every member listed in the workspace manifest, merging their results
+///
+/// When `--keep-going` is used a member which fails is recorded in `failures` and the remaining
+/// members are still checked, otherwise the first failure is returned. Members with an invalid
+/// manifest are recorded in `skipped` and never stop the others.
+fn check_workspace(
+    cfg: &Config,
+    failures: &mut Vec<(String, CliError)>,
+    skipped: &mut Vec<String>,
+) -> CliResult<Vec<DependencyStatus>> {
+    let ws_manifest = cargo_files::WorkspaceManifest::from_manifest_path(&cfg.manifest)?;
+    let members = if cfg.all_members {
+        ws_manifest.members(&cfg.manifest)
+    } else {
+        ws_manifest.default_members(&cfg.manifest)
+    };
+    if members.is_empty() {
+        return Err(CliError::Generic(format!(
+            "No workspace members found: {} does not list any",
+            cfg.manifest.display()
+        )));
+    }
+
+    let member_name = |member: &str| {
+        let manifest = cargo_files::WorkspaceManifest::member_manifest_path(&cfg.manifest, member);
+        cargo_files::WorkspaceManifest::from_manifest_path(&manifest)
+            .ok()
+            .and_then(|m| m.name().map(|n| n.to_owned()))
+    };
+    for excluded in &cfg.exclude_members {
+        let known = ws_manifest.members(&cfg.manifest).iter().any(|member| {
+            member == excluded || member_name(member).as_deref() == Some(*excluded)
+        });
+        if !known {
+            wlnerr!(
+                "{} '{}' from --exclude-members is not a member of the workspace",
+                Format::Warning("warning:"),
+                excluded
+            );
+        }
+    }
+
+    if checked_members(cfg)?.is_empty() {
+        return Err(CliError::Generic(format!(
+            "No workspace members found: every member of {} is excluded by --exclude-members",
+            cfg.manifest.display()
+        )));
+    }
+
+    // Each member gets its own temporary project, so they can be resolved at the same time.
+    // Workers take the next member from the queue until it's empty, or a member failed without
+    // `--keep-going`.
+    let resume_key = if cfg.resume {
+        let key = cache::key(cfg)?;
+        cache::start_members(cfg, key)?;
+        Some(key)
+    } else {
+        None
+    };
+
+    let queue = Mutex::new(members.into_iter().enumerate().collect::<VecDeque<_>>());
+    let results = Mutex::new(vec![]);
+    let failed = AtomicBool::new(false);
+    crossbeam::scope(|scope| for _ in 0..cfg.jobs {
+        scope.spawn(|| loop {
+            if failed.load(Ordering::SeqCst) && !cfg.keep_going {
+                break;
+            }
+            let (i, member) = match queue.lock().unwrap().pop_front() {
+                Some(next) => next,
+                None => break,
+            };
+            let res = match resume_key {
+                Some(key) => resume_member(cfg, key, &member),
+                None => check_member(cfg, &member),
+            };
+            match res {
+                Ok(_) | Err(CliError::InvalidManifest(_)) => (),
+                Err(_) => failed.store(true, Ordering::SeqCst),
+            }
+            results.lock().unwrap().push((i, member, res));
+        });
+    });
+
+    // Merge in workspace order so the first failure is the same whatever the number of jobs
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|&(i, _, _)| i);
+    let mut statuses = vec![];
+    for (_, member, res) in results {
+        match res {
+            Ok(member_statuses) => statuses.extend(member_statuses),
+            // One unusual manifest shouldn't stop the rest of the workspace from being checked
+            Err(CliError::InvalidManifest(e)) => {
+                wlnerr!(
+                    "{} skipping workspace member '{}': {}",
+                    Format::Warning("warning:"),
+                    member,
+                    e
+                );
+                skipped.push(member);
+            }
+            Err(e) => if cfg.keep_going {
+                failures.push((member, e));
+            } else {
+                return Err(e);
+            },
+        }
+    }
+    // Dependencies shared by several members are reported once, unless each member gets a report
+    statuses.sort();
+    if !cfg.no_aggregate {
+        statuses.dedup();
+    }
+    if let Some(key) = resume_key {
+        if failures.is_empty() {
+            cache::clear_members(cfg, key)?;
+        }
+    }
+
+    Ok(statuses)
+}
+
+/// Checks a single workspace member, given its path relative to the workspace manifest
+fn check_member(cfg: &Config, member: &str) -> CliResult<Vec<DependencyStatus>> {
+    let manifest = cargo_files::WorkspaceManifest::member_manifest_path(&cfg.manifest, member);
+    let m = cargo_files::WorkspaceManifest::from_manifest_path(&manifest)?;
+    let name = m.name().unwrap_or(member);
+    if cfg.exclude_members.contains(&name) || cfg.exclude_members.contains(&member) {
+        verboseln!(cfg, "Skipping excluded workspace member {}", Format::Warning(member));
+        return Ok(vec![]);
+    }
+    verboseln!(cfg, "Checking workspace member {}...", Format::Warning(member));
+    check_project(cfg, &manifest, &cfg.lockfile, Some(name))
+}
+
+/// Like `check_member`, but reusing the results saved for `member` by an interrupted `--resume`
+/// run, and saving them otherwise
+fn resume_member(cfg: &Config, key: u64, member: &str) -> CliResult<Vec<DependencyStatus>> {
+    if let Some(statuses) = cache::load_member(cfg, key, member) {
+        verboseln!(cfg, "Using saved results for workspace member {}", Format::Good(member));
+        return Ok(statuses);
+    }
+    let statuses = check_member(cfg, member)?;
+    cache::store_member(cfg, key, member, &statuses)?;
+    Ok(statuses)
+}
+
+/// Compares the current, SemVer compatible and latest dependencies of a single package
+fn check_project(
+    cfg: &Config,
+    manifest: &Path,
+    lockfile: &Path,
+    root: Option<&str>,
+) -> CliResult<Vec<DependencyStatus>> {
     // parse original lockfile
     verbose!(
         cfg,
         "Parsing {}...",
-        Format::Warning(cfg.lockfile.to_string_lossy())
+        Format::Warning(lockfile.to_string_lossy())
     );
     let dep_tree_curr = {
-        let mut parsed_lock = cargo_files::Lockfile::from_lockfile_path(&cfg.lockfile)?;
+        let mut parsed_lock = cargo_files::Lockfile::from_lockfile_path(lockfile)?;
         if parsed_lock.package.is_none() {
             return Err(CliError::NoRootDeps);
         }
@@ -176,84 +932,1026 @@ fn execute(m: &ArgMatches) -> CliResult<i32> {
             .as_mut()
             .unwrap()
             .push(parsed_lock.root.clone());
-        cargo_files::DependencyTree::from_lockfile(&mut parsed_lock, cfg.root, cfg.depth)
+        cargo_files::DependencyTree::from_lockfile(&mut parsed_lock, root, cfg.depth)
     };
     verboseln!(cfg, "{}", Format::Good("Done"));
     // create a temp project in tmp
-    let tmp_proj = cargo_ops::TempProject::new(&cfg.manifest, &cfg.lockfile)?;
-    // write semver to the tmp Cargo.toml
-    tmp_proj.write_manifest_semver()?;
-    // update it
-    tmp_proj.cargo_update()?;
+    // Members are checked in a project each, which can't share a directory
+    let temp_name = cfg.temp_name.as_ref().map(|name| {
+        let mut name = name.clone();
+        if let (Some(member), true) = (root, cfg.workspace) {
+            name.push("-");
+            name.push(member);
+        }
+        name
+    });
+    let mut tmp_proj = cargo_ops::TempProject::new(
+        manifest,
+        lockfile,
+        temp_name.as_ref().map(|n| &n[..]),
+    )?;
+    if temp_name.is_some() {
+        verboseln!(
+            cfg,
+            "Keeping the temporary project {}",
+            Format::Good(tmp_proj.manifest.to_string_lossy())
+        );
+    }
+    tmp_proj.use_cargo(&cfg.cargo);
+    if let Some(secs) = cfg.registry_timeout {
+        tmp_proj.use_http_timeout(secs);
+    }
+    let other_platforms = match cfg.target {
+        Some(ref target) => tmp_proj.use_target(target),
+        None => HashSet::new(),
+    };
+    if let Some(version) = cfg.assume_version {
+        tmp_proj.assume_version(version);
+    }
+    let requirements = tmp_proj.requirements();
+    let kinds = tmp_proj.kinds();
+    let patched = tmp_proj.patched();
+    let declared = tmp_proj.declared();
+    // write semver to the tmp Cargo.toml and update it
+    let compat_res = tmp_proj
+        .write_manifest_semver()
+        .and_then(|_| tmp_proj.cargo_update());
     // parse lockfile with semver compatible dependencies
-    verbose!(
-        cfg,
-        "Parsing semver compatible lockfile {}...",
-        Format::Warning(tmp_proj.lockfile.to_string_lossy())
-    );
-    let dep_tree_compat = {
-        let mut parsed_lock = cargo_files::Lockfile::from_lockfile_path(&tmp_proj.lockfile)?;
-        parsed_lock
-            .package
-            .as_mut()
-            .unwrap()
-            .push(parsed_lock.root.clone());
-        cargo_files::DependencyTree::from_lockfile(&mut parsed_lock, cfg.root, -1)
+    let dep_tree_compat = match compat_res {
+        Ok(()) => {
+            verbose!(
+                cfg,
+                "Parsing semver compatible lockfile {}...",
+                Format::Warning(tmp_proj.lockfile.to_string_lossy())
+            );
+            let tree = parse_tree(&tmp_proj.lockfile, root, -1)?;
+            verboseln!(cfg, "{}", Format::Good("Done"));
+            Some(tree)
+        }
+        Err(e) => {
+            wlnerr!(
+                "{} couldn't find SemVer compatible updates, only the latest versions will be \
+                 shown: {}",
+                Format::Warning("warning:"),
+                e.message()
+            );
+            None
+        }
     };
-    verboseln!(cfg, "{}", Format::Good("Done"));
-    // rewrite the manifest with "*" semver dependencies
-    tmp_proj.write_manifest_latest()?;
-    // update it
-    tmp_proj.cargo_update()?;
+    // rewrite the manifest with "*" semver dependencies and update it
+    // The SemVer compatible versions were resolved from the project's usual registry
+    if let Some(url) = cfg.registry_index {
+        tmp_proj.use_registry_index(url);
+    }
+    let latest_res = tmp_proj
+        .write_manifest_latest(cfg.compatible_latest)
+        .and_then(|_| tmp_proj.cargo_update());
     // parse lockfile with latest dependencies
-    verbose!(
-        cfg,
-        "Parsing latest lockfile {}...",
-        Format::Warning(tmp_proj.lockfile.to_string_lossy())
-    );
-    let dep_tree_latest = {
-        let mut parsed_lock = cargo_files::Lockfile::from_lockfile_path(&tmp_proj.lockfile)?;
-        parsed_lock
-            .package
-            .as_mut()
-            .unwrap()
-            .push(parsed_lock.root.clone());
-        cargo_files::DependencyTree::from_lockfile(&mut parsed_lock, cfg.root, -1)
+    let dep_tree_latest = match latest_res {
+        Ok(()) => {
+            verbose!(
+                cfg,
+                "Parsing latest lockfile {}...",
+                Format::Warning(tmp_proj.lockfile.to_string_lossy())
+            );
+            let tree = parse_tree(&tmp_proj.lockfile, root, -1)?;
+            verboseln!(cfg, "{}", Format::Good("Done"));
+            if cfg.duplicates || cfg.graph_delta {
+                // The current tree may be limited by --depth, these compare all of it
+                let full_curr = parse_tree(lockfile, root, -1)?;
+                if cfg.duplicates {
+                    for (name, versions) in
+                        cargo_files::DependencyTree::new_duplicates(&full_curr, &tree)
+                    {
+                        wlnerr!(
+                            "{} the latest versions would need more than one version of {}: {}",
+                            Format::Warning("warning:"),
+                            name,
+                            versions.join(", ")
+                        );
+                    }
+                }
+                if cfg.graph_delta {
+                    let (added, removed) =
+                        cargo_files::DependencyTree::package_delta(&full_curr, &tree);
+                    wlnerr!(
+                        "{} the latest versions would need {} packages instead of {} ({} added, {} \
+                         removed)",
+                        Format::Warning("note:"),
+                        tree.package_ids().len(),
+                        full_curr.package_ids().len(),
+                        added,
+                        removed
+                    );
+                }
+            }
+            Some(tree)
+        }
+        Err(e) => {
+            if dep_tree_compat.is_none() {
+                return Err(e);
+            }
+            wlnerr!(
+                "{} couldn't find the latest versions, only SemVer compatible updates will be \
+                 shown: {}",
+                Format::Warning("warning:"),
+                e.message()
+            );
+            None
+        }
+    };
+    // A pass which failed is compared as if nothing had changed, so its column stays empty
+    let dep_tree_compat = match dep_tree_compat {
+        Some(tree) => tree,
+        None => parse_tree(lockfile, root, -1)?,
+    };
+    let dep_tree_latest = match dep_tree_latest {
+        Some(tree) => tree,
+        None => parse_tree(lockfile, root, -1)?,
     };
-    verboseln!(cfg, "{}", Format::Good("Done"));
 
-    let mut tw = TabWriter::new(vec![]);
-    write!(&mut tw, "Name\tProject Ver\tSemVer Compat\tLatest Ver\n")
-        .unwrap_or_else(|e| panic!("write! error: {}", e));
-    let lines = cargo_files::DependencyTree::print_list_to_vec(
+    if let Some(ref dir) = cfg.graph_dir {
+        write_graphs(
+            dir,
+            &[
+                ("current", &dep_tree_curr),
+                ("compat", &dep_tree_compat),
+                ("latest", &dep_tree_latest),
+            ],
+        )?;
+    }
+
+    let mut statuses = cargo_files::DependencyTree::compare_versions(
         &dep_tree_curr,
         &dep_tree_compat,
         &dep_tree_latest,
-        &cfg,
+        &requirements,
+        &kinds,
+        &patched,
+        cfg,
     );
-    if lines.is_empty() {
-        println!("All dependencies are up to date, yay!");
-        return Ok(0);
-    }
-    for l in lines {
-        try!(write!(&mut tw, "{}", l));
-    }
-    tw.flush()
-        .unwrap_or_else(|e| panic!("failed to flush TabWriter: {}", e));
-    write!(
-        stdout(),
-        "{}",
-        String::from_utf8(tw.into_inner().unwrap())
-            .unwrap_or_else(|e| panic!("from_utf8 error: {}", e))
-    ).unwrap_or_else(|e| panic!("write! error: {}", e));
-
-    Ok(cfg.exit_code)
+    // Dependencies only used on other platforms aren't in the temporary manifests at all
+    statuses.retain(|s| s.path.get(1).is_none_or(|d| !other_platforms.contains(d)));
+    if cfg.only_explicit {
+        statuses.retain(|s| s.parent.is_none() && declared.contains(&s.name));
+    }
+    if cfg.changed_only {
+        let changed = git::changed_dependencies(manifest)?;
+        statuses.retain(|s| s.parent.is_none() && changed.contains(&s.name));
+    }
+
+    Ok(statuses)
+}
+
+/// Writes each graph to `<root>-<name>.dot` in `dir`, see `--graph-dir`
+fn write_graphs(dir: &Path, graphs: &[(&str, &cargo_files::DependencyTree)]) -> CliResult<()> {
+    fs::create_dir_all(dir)?;
+    for &(name, tree) in graphs {
+        let root = tree.root.upgrade().unwrap();
+        let path = dir.join(format!("{}-{}.dot", root.borrow().name, name));
+        let mut file = File::create(&path)?;
+        write!(file, "{}", tree.to_dot())?;
+    }
+    Ok(())
+}
+
+/// The `--cargo-path` given on the command line, or `cargo` from the PATH
+///
+/// `--version` is handled by clap while parsing, so the argument is looked up before then.
+fn cargo_path_arg() -> OsString {
+    let mut args = env::args_os();
+    while let Some(arg) = args.next() {
+        if arg == "--cargo-path" {
+            return args.next().unwrap_or_else(|| OsString::from("cargo"));
+        }
+        if let Some(path) = arg.to_str().and_then(|a| a.strip_prefix("--cargo-path=")) {
+            return OsString::from(path);
+        }
+    }
+    OsString::from("cargo")
+}
+
+/// Our version along with that of the `cargo` used for `cargo update`, since the results depend
+/// on both
+fn version_with_cargo(cargo: &OsStr) -> String {
+    let cargo_version = process::Command::new(cargo)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|v| v.trim().to_owned())
+        .unwrap_or_else(|| "cargo not found".to_owned());
+    format!("v{} ({})", crate_version!(), cargo_version)
+}
+
+fn parse_tree(
+    lockfile: &Path,
+    root: Option<&str>,
+    depth: i32,
+) -> CliResult<cargo_files::DependencyTree> {
+    let mut parsed_lock = cargo_files::Lockfile::from_lockfile_path(lockfile)?;
+    parsed_lock
+        .package
+        .as_mut()
+        .unwrap()
+        .push(parsed_lock.root.clone());
+    Ok(cargo_files::DependencyTree::from_lockfile(&mut parsed_lock, root, depth))
 }
 
-fn is_file(s: String) -> Result<(), String> {
-    let p = Path::new(&*s);
+fn is_file(s: &OsStr) -> Result<(), OsString> {
+    let p = Path::new(s);
     if p.file_name().is_none() {
-        return Err(format!("'{}' doesn't appear to be a valid file name", &*s));
+        return Err(format!("'{}' doesn't appear to be a valid file name", p.display()).into());
     }
     Ok(())
 }
+
+fn is_file_or_dir(s: &OsStr) -> Result<(), OsString> {
+    if Path::new(s).is_dir() {
+        return Ok(());
+    }
+    is_file(s)
+}
+
+fn is_registry_url(s: String) -> Result<(), String> {
+    const SCHEMES: &[&str] = &[
+        "https://",
+        "http://",
+        "file://",
+        "ssh://",
+        "git://",
+        "sparse+https://",
+        "sparse+http://",
+    ];
+    if SCHEMES.iter().any(|scheme| s.starts_with(scheme)) {
+        return Ok(());
+    }
+    Err(format!(
+        "'{}' doesn't appear to be a registry URL (expected one of the schemes {})",
+        &*s,
+        SCHEMES.join(", ")
+    ))
+}
+
+fn is_executable(s: &OsStr) -> Result<(), OsString> {
+    if util::is_executable(Path::new(s)) {
+        return Ok(());
+    }
+    Err(format!("'{}' isn't an executable file", Path::new(s).display()).into())
+}
+
+fn is_version(s: String) -> Result<(), String> {
+    match version::Version::parse(&s) {
+        Some(_) => Ok(()),
+        None => Err(format!("'{}' isn't a valid version (expected X.Y.Z)", &*s)),
+    }
+}
+
+fn is_jobs(s: String) -> Result<(), String> {
+    match s.parse::<usize>() {
+        Ok(0) | Err(..) => Err(format!("'{}' isn't a valid number of jobs", &*s)),
+        Ok(_) => Ok(()),
+    }
+}
+
+fn is_temp_name(s: &OsStr) -> Result<(), OsString> {
+    // A single plain directory name, so the project can't end up outside the temporary directory
+    let mut components = Path::new(s).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(()),
+        _ => Err(format!("'{}' isn't a valid directory name", Path::new(s).display()).into()),
+    }
+}
+
+fn is_timeout(s: String) -> Result<(), String> {
+    match s.parse::<u64>() {
+        Ok(0) | Err(..) => Err(format!("'{}' isn't a valid number of seconds", &*s)),
+        Ok(_) => Ok(()),
+    }
+}
+
+fn is_exit_code(s: String) -> Result<(), String> {
+    match s.parse::<i32>() {
+        Ok(error::EXIT_ERROR) => Err(format!(
+            "{} is reserved for errors in cargo-outdated itself",
+            error::EXIT_ERROR
+        )),
+        Ok(error::EXIT_UNAPPROVED) => Err(format!(
+            "{} is reserved for packages which are not on the --allowlist",
+            error::EXIT_UNAPPROVED
+        )),
+        Ok(_) => Ok(()),
+        Err(..) => Err(format!("'{}' isn't a valid exit code", &*s)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_files::Update;
+    use fixtures::{self, lockfile, Project};
+
+    use super::*;
+
+    /// A project `p` depending on `x = "1"`, currently resolved to x 1.0.0
+    fn project() -> Project {
+        let project = Project::new();
+        project.file(
+            "Cargo.toml",
+            "[package]\nname = \"p\"\nversion = \"0.1.0\"\n[dependencies]\nx = \"1\"\n",
+        );
+        project.file(
+            "Cargo.lock",
+            &lockfile(&[("p", "0.1.0", &["x 1.0.0"]), ("x", "1.0.0", &[])]),
+        );
+        project
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn assumed_version_is_resolved_without_reporting_the_root() {
+        let project = project();
+        // The SemVer compatible versions only resolve with the assumed version
+        project.cargo_by(&[
+            (
+                "\"*\"",
+                &lockfile(&[("p", "0.2.0", &["x 2.0.0"]), ("x", "2.0.0", &[])]),
+            ),
+            (
+                "version = \"0.2.0\"",
+                &lockfile(&[("p", "0.2.0", &["x 1.1.0"]), ("x", "1.1.0", &[])]),
+            ),
+        ]);
+        let m = fixtures::matches(&project.args(&["--assume-version", "0.2.0"]));
+        let cfg = Config::from_matches(&m).unwrap();
+
+        let statuses = check_project(&cfg, &cfg.manifest, &cfg.lockfile, None).unwrap();
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].name, "x");
+        assert_eq!(statuses[0].compat, Update::Version("1.1.0".to_owned()));
+        assert_eq!(statuses[0].latest, Update::Version("2.0.0".to_owned()));
+    }
+
+    /// The report written by `cargo outdated` with `args`, along with its exit code
+    fn run(args: &[String]) -> (String, i32) {
+        let m = fixtures::matches(args);
+        let cfg = Config::from_matches(&m).unwrap();
+        let mut out = vec![];
+        let code = check(&cfg, &mut out).unwrap();
+        (String::from_utf8(out).unwrap(), code)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn requirement_column_shows_each_kind_of_requirement() {
+        let project = Project::new();
+        project.file(
+            "Cargo.toml",
+            "[package]\nname = \"p\"\nversion = \"0.1.0\"\n\
+             [dependencies]\na = \"1.2\"\nb = \"~1.2\"\n\
+             renamed = { package = \"c\", version = \"=1.2.3\" }\n",
+        );
+        let packages = |a: &'static str, b: &'static str, c: &'static str| {
+            lockfile(&[
+                ("p", "0.1.0", &[&*format!("a {}", a), &*format!("b {}", b), &*format!("c {}", c)]),
+                ("a", a, &[]),
+                ("b", b, &[]),
+                ("c", c, &[]),
+            ])
+        };
+        project.file("Cargo.lock", &packages("1.2.0", "1.2.0", "1.2.3"));
+        project.cargo(
+            &packages("1.4.0", "1.2.5", "1.2.3"),
+            &packages("2.0.0", "2.0.0", "2.0.0"),
+        );
+        let (report, _) = run(&project.args(&["--format", "markdown"]));
+        let rows: Vec<_> = report.lines().skip(2).take(3).collect();
+        assert_eq!(
+            rows,
+            vec![
+                "| a | 1.2 | 1.2.0 | 1.4.0 | 2.0.0 |",
+                "| b | ~1.2 | 1.2.0 | 1.2.5 | 2.0.0 |",
+                "| c | =1.2.3 | 1.2.3 | -- | 2.0.0 |",
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn group_by_kind_has_a_section_per_kind() {
+        let project = Project::new();
+        project.file(
+            "Cargo.toml",
+            "[package]\nname = \"p\"\nversion = \"0.1.0\"\n\
+             [dependencies]\na = \"1\"\n\
+             [dev-dependencies]\nrenamed = { package = \"b\", version = \"1\" }\n\
+             [build-dependencies]\nc = \"1\"\n",
+        );
+        let packages = |version: &'static str| {
+            let deps = ["a", "b", "c"]
+                .iter()
+                .map(|d| format!("{} {}", d, version))
+                .collect::<Vec<_>>();
+            let deps: Vec<_> = deps.iter().map(|d| &**d).collect();
+            lockfile(&[
+                ("p", "0.1.0", &deps),
+                ("a", version, &[]),
+                ("b", version, &[]),
+                ("c", version, &[]),
+            ])
+        };
+        project.file("Cargo.lock", &packages("1.0.0"));
+        project.cargo(&packages("1.0.0"), &packages("2.0.0"));
+        let (report, _) = run(&project.args(&["--format", "markdown", "--group-by", "kind"]));
+        let sections: Vec<_> = report
+            .lines()
+            .filter(|l| l.starts_with("### ") || l.starts_with("| ") && !l.starts_with("| Name"))
+            .collect();
+        assert_eq!(
+            sections,
+            vec![
+                "### Dependencies",
+                "| a | 1 | 1.0.0 | -- | 2.0.0 |",
+                "### Dev dependencies",
+                "| b | 1 | 1.0.0 | -- | 2.0.0 |",
+                "### Build dependencies",
+                "| c | 1 | 1.0.0 | -- | 2.0.0 |",
+            ]
+        );
+    }
+
+    /// `project` with x 1.1.0 as its SemVer compatible version and 2.0.0 as its latest
+    #[cfg(unix)]
+    fn outdated_project() -> Project {
+        let project = project();
+        project.cargo(
+            &lockfile(&[("p", "0.1.0", &["x 1.1.0"]), ("x", "1.1.0", &[])]),
+            &lockfile(&[("p", "0.1.0", &["x 2.0.0"]), ("x", "2.0.0", &[])]),
+        );
+        project
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exit_code_with_json_format() {
+        let project = outdated_project();
+        let (report, code) = run(&project.args(&["--format", "json", "--exit-code", "3"]));
+        assert_eq!(code, 3);
+        assert!(report.starts_with("{\"summary\": {\"total\": 1,"));
+        assert!(report.contains("\"name\": \"x\""));
+    }
+
+    #[test]
+    fn each_globbed_project_has_its_own_temp_name() {
+        let project = Project::new();
+        let a = project.file("a/Cargo.toml", "[package]\nname = \"a\"\nversion = \"0.1.0\"\n");
+        let b = project.file("b/Cargo.toml", "[package]\nname = \"b\"\nversion = \"0.1.0\"\n");
+        let pattern = project.path("*/Cargo.toml").display().to_string();
+        let m = fixtures::matches(&["-m", &pattern, "--temp-name", "kept"]);
+
+        let temp_name = |manifest| Config::for_manifest(&m, Some(manifest)).unwrap().temp_name;
+        assert_eq!(temp_name(a), Some(OsString::from("kept-a")));
+        assert_eq!(temp_name(b), Some(OsString::from("kept-b")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn glob_checks_each_project_it_matches_into_one_report() {
+        let project = Project::new();
+        let resolved = |name: &str, dep: &str, version: &str| {
+            let dep_version = format!("{} {}", dep, version);
+            lockfile(&[(name, "0.1.0", &[&*dep_version]), (dep, version, &[])])
+        };
+        for &(name, dep) in &[("a", "x"), ("b", "y")] {
+            project.file(
+                &format!("{}/Cargo.toml", name),
+                &format!(
+                    "[package]\nname = \"{}\"\nversion = \"0.1.0\"\n[dependencies]\n{} = \"1\"\n",
+                    name, dep
+                ),
+            );
+            project.file(&format!("{}/Cargo.lock", name), &resolved(name, dep, "1.0.0"));
+        }
+        let (a_latest, b_latest) = (resolved("a", "x", "2.0.0"), resolved("b", "y", "2.0.0"));
+        let (a_compat, b_compat) = (resolved("a", "x", "1.1.0"), resolved("b", "y", "1.0.0"));
+        let cargo = project.cargo_by(&[
+            ("x = \"*\"", &a_latest),
+            ("y = \"*\"", &b_latest),
+            ("name = \"a\"", &a_compat),
+            ("name = \"b\"", &b_compat),
+        ]);
+        // Unique to this run, since the temporary directory is shared with any other run
+        let temp_name = format!("{}-glob", project.path("").file_name().unwrap().to_string_lossy());
+        let report = project.path("report.txt");
+        let m = fixtures::matches(&[
+            "-m",
+            &project.path("*/Cargo.toml").display().to_string(),
+            "--cargo-path",
+            &cargo.display().to_string(),
+            "--temp-name",
+            &temp_name,
+            "--report-path",
+            &report.display().to_string(),
+            "--format",
+            "plain",
+            // Writing to a file would turn colors off for the other tests running alongside
+            "--color",
+            "always",
+        ]);
+
+        assert_eq!(execute(&m).unwrap(), 0);
+        let report = fs::read_to_string(report).unwrap();
+        let position = |text| report.find(text).unwrap();
+        assert!(position("a/Cargo.toml") < position("\nx 1.0.0 1.1.0 2.0.0\n"));
+        assert!(position("\nx 1.0.0 1.1.0 2.0.0\n") < position("b/Cargo.toml"));
+        assert!(position("b/Cargo.toml") < position("\ny 1.0.0 -- 2.0.0\n"));
+        // Each project is resolved in a temporary directory of its own
+        let dirs: Vec<_> = ["a", "b"]
+            .iter()
+            .map(|dir| env::temp_dir().join(format!("{}-{}", temp_name, dir)))
+            .collect();
+        let log = project.cargo_log();
+        assert_eq!(log.len(), 4);
+        assert!(log[..2].iter().all(|l| l.contains(&*dirs[0].to_string_lossy())));
+        assert!(log[2..].iter().all(|l| l.contains(&*dirs[1].to_string_lossy())));
+        for dir in dirs {
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn report_path_receives_the_report() {
+        let project = outdated_project();
+        let report = project.path("reports/outdated.json");
+        let path = report.display().to_string();
+        // Writing to a file would turn colors off for the other tests running alongside
+        let args = project.args(&["--format", "json", "--report-path", &path, "--color", "always"]);
+
+        assert_eq!(execute(&fixtures::matches(&args)).unwrap(), 0);
+        let report = fs::read_to_string(report).unwrap();
+        assert!(report.starts_with("{\"summary\": {\"total\": 1,"));
+        assert!(report.contains("\"name\": \"x\""));
+        assert!(report.contains("\"latest\": \"2.0.0\""));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn second_run_hits_the_cache() {
+        let project = outdated_project();
+        let args = project.args(&["--cache"]);
+        let first = run(&args);
+        assert_eq!(project.cargo_log().len(), 2);
+
+        assert_eq!(run(&args), first);
+        assert_eq!(project.cargo_log().len(), 2);
+        assert!(project.path("target/cargo-outdated").is_dir());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn project_defaults_set_the_format() {
+        let project = outdated_project();
+        project.file(".outdated.toml", "format = \"plain\"\n");
+        assert_eq!(run(&project.args(&[])).0, "x 1.0.0 1.1.0 2.0.0\n");
+        // The command line takes precedence
+        let (report, _) = run(&project.args(&["--format", "json"]));
+        assert!(report.starts_with("{\"summary\""));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn project_defaults_ignore_dependencies() {
+        let project = Project::new();
+        project.file(
+            "Cargo.toml",
+            "[package]\nname = \"p\"\nversion = \"0.1.0\"\n[dependencies]\nx = \"1\"\ny = \"1\"\n",
+        );
+        let packages = |version: &'static str| {
+            let deps = [format!("x {}", version), format!("y {}", version)];
+            lockfile(&[
+                ("p", "0.1.0", &[&*deps[0], &*deps[1]]),
+                ("x", version, &[]),
+                ("y", version, &[]),
+            ])
+        };
+        project.file("Cargo.lock", &packages("1.0.0"));
+        project.cargo(&packages("1.0.0"), &packages("2.0.0"));
+        project.file(".outdated.toml", "format = \"plain\"\nignore = [\"x\"]\n");
+
+        assert_eq!(run(&project.args(&[])).0, "y 1.0.0 -- 2.0.0\n");
+        assert_eq!(run(&project.args(&["--ignore", "y"])).0, "x 1.0.0 -- 2.0.0\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn interactive_prompt_applies_the_chosen_updates() {
+        let project = Project::new();
+        project.file(
+            "Cargo.toml",
+            "[package]\nname = \"p\"\nversion = \"0.1.0\"\n[dependencies]\nx = \"1\"\ny = \"1\"\n",
+        );
+        let packages = |x: &'static str, y: &'static str| {
+            let deps = [format!("x {}", x), format!("y {}", y)];
+            lockfile(&[
+                ("p", "0.1.0", &[&*deps[0], &*deps[1]]),
+                ("x", x, &[]),
+                ("y", y, &[]),
+            ])
+        };
+        project.file("Cargo.lock", &packages("1.0.0", "1.0.0"));
+        project.cargo(&packages("1.1.0", "1.0.5"), &packages("1.1.0", "1.0.5"));
+        let m = fixtures::matches(&project.args(&["--interactive"]));
+        let cfg = Config::from_matches(&m).unwrap();
+        let statuses = check_project(&cfg, &cfg.manifest, &cfg.lockfile, None).unwrap();
+
+        let mut output = vec![];
+        apply_interactively(&statuses, &cfg, &mut &b"y\nno\n"[..], &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("Update x from 1.0.0 to 1.1.0? [y/N] "));
+        assert!(output.ends_with("Update y from 1.0.0 to 1.0.5? [y/N] "));
+        let updates: Vec<_> = project
+            .cargo_log()
+            .into_iter()
+            .filter(|l| l.contains("--precise"))
+            .collect();
+        assert_eq!(updates.len(), 1);
+        assert!(updates[0].ends_with(" -p x:1.0.0 --precise 1.1.0"));
+
+        // Nothing is updated once the input runs out
+        let mut output = vec![];
+        apply_interactively(&statuses, &cfg, &mut &b""[..], &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "Update x from 1.0.0 to 1.1.0? [y/N] ");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn locked_patch_behind_a_compatible_release() {
+        let resolved = |version: &str| {
+            lockfile(&[("p", "0.1.0", &[&*format!("x {}", version)]), ("x", version, &[])])
+        };
+        let behind = project();
+        behind.cargo(&resolved("1.2.0"), &resolved("1.2.0"));
+        assert_eq!(run(&behind.args(&["--format", "plain"])).0, "x 1.0.0 1.2.0 1.2.0\n");
+
+        // Already the newest release, even though the requirement allows newer ones
+        let newest = project();
+        newest.cargo(&resolved("1.0.0"), &resolved("1.0.0"));
+        assert_eq!(run(&newest.args(&["--format", "plain"])), (String::new(), 0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn manifest_path_may_be_a_directory() {
+        let project = outdated_project();
+        let mut args = project.args(&["--format", "plain"]);
+        args[1] = project.path("").display().to_string();
+        let m = fixtures::matches(&args);
+        assert_eq!(Config::from_matches(&m).unwrap().manifest, project.path("Cargo.toml"));
+        assert_eq!(run(&args).0, "x 1.0.0 1.1.0 2.0.0\n");
+
+        // A directory without a manifest
+        args[1] = project.path("fake-cargo").display().to_string();
+        let m = fixtures::matches(&args);
+        assert_eq!(
+            Config::from_matches(&m).err().unwrap().message(),
+            format!("Could not find `Cargo.toml` in `{}`", project.path("fake-cargo").display())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exit_codes_tell_the_outcomes_apart() {
+        let resolved = |version: &str| {
+            lockfile(&[("p", "0.1.0", &[&*format!("x {}", version)]), ("x", version, &[])])
+        };
+        let up_to_date = project();
+        up_to_date.cargo(&resolved("1.0.0"), &resolved("1.0.0"));
+        assert_eq!(run(&up_to_date.args(&["--exit-code", "3"])).1, 0);
+
+        let outdated = outdated_project();
+        assert_eq!(run(&outdated.args(&["--exit-code", "3"])).1, 3);
+
+        // Neither version could be resolved
+        let failing = project();
+        failing.cargo_by(&[]);
+        let m = fixtures::matches(&failing.args(&["--exit-code", "3"]));
+        let cfg = Config::from_matches(&m).unwrap();
+        let e = check(&cfg, &mut vec![]).err().unwrap();
+        assert_eq!(e.exit_code(), error::EXIT_ERROR);
+        assert_eq!(error::EXIT_ERROR, 101);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn no_exit_code_overrides_every_exit_code() {
+        let project = outdated_project();
+        assert_eq!(run(&project.args(&["--exit-code", "3", "--no-exit-code"])).1, 0);
+        project.file(".outdated.toml", "exit-code = 3\n");
+        assert_eq!(run(&project.args(&[])).1, 3);
+        assert_eq!(run(&project.args(&["--no-exit-code"])).1, 0);
+    }
+
+    /// A workspace of `members`, each with its dependencies, all of which are at 1.0.0 and have
+    /// 1.1.0 as their SemVer compatible version and 2.0.0 as their latest
+    #[cfg(unix)]
+    fn workspace(root: &str, members: &[(&str, &[&str])]) -> Project {
+        let project = Project::new();
+        project.file("Cargo.toml", root);
+        for &(member, deps) in members {
+            let deps: String = deps.iter().map(|d| format!("{} = \"1\"\n", d)).collect();
+            project.file(
+                &format!("{}/Cargo.toml", member),
+                &format!(
+                    "[package]\nname = \"{}\"\nversion = \"0.1.0\"\n[dependencies]\n{}",
+                    member, deps
+                ),
+            );
+        }
+        let resolved = |version: &str| {
+            let mut deps: Vec<_> = members.iter().flat_map(|&(_, deps)| deps.iter()).collect();
+            deps.sort();
+            deps.dedup();
+            let member_deps: Vec<Vec<_>> = members
+                .iter()
+                .map(|&(_, deps)| deps.iter().map(|d| format!("{} {}", d, version)).collect())
+                .collect();
+            let member_deps: Vec<Vec<_>> = member_deps
+                .iter()
+                .map(|deps| deps.iter().map(|d| &**d).collect())
+                .collect();
+            let mut packages: Vec<_> = members
+                .iter()
+                .zip(&member_deps)
+                .map(|(&(member, _), deps)| (member, "0.1.0", &deps[..]))
+                .collect();
+            packages.extend(deps.iter().map(|d| (**d, version, &[][..])));
+            let members: Vec<_> = members.iter().map(|&(member, _)| member).collect();
+            fixtures::workspace_lockfile(&packages, &members)
+        };
+        project.file("Cargo.lock", &resolved("1.0.0"));
+        project.cargo(&resolved("1.1.0"), &resolved("2.0.0"));
+        project
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn excluded_members_are_skipped() {
+        let project = workspace(
+            "[workspace]\nmembers = [\"a\", \"b\", \"c\"]\n",
+            &[("a", &["x"]), ("b", &["y"]), ("c", &["z"])],
+        );
+        let args = ["--workspace", "--exclude-members", "b", "--format", "plain"];
+        assert_eq!(
+            run(&project.args(&args)).0,
+            "x 1.0.0 1.1.0 2.0.0\nz 1.0.0 1.1.0 2.0.0\n"
+        );
+        // The other members were resolved twice each, b never was
+        assert_eq!(project.cargo_log().len(), 4);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn only_default_members_are_checked_unless_all_members() {
+        let project = workspace(
+            "[workspace]\nmembers = [\"a\", \"b\", \"c\"]\ndefault-members = [\"a\", \"c\"]\n",
+            &[("a", &["x"]), ("b", &["y"]), ("c", &["z"])],
+        );
+        let args = ["--workspace", "--format", "plain"];
+        assert_eq!(
+            run(&project.args(&args)).0,
+            "x 1.0.0 1.1.0 2.0.0\nz 1.0.0 1.1.0 2.0.0\n"
+        );
+        assert_eq!(project.cargo_log().len(), 4);
+
+        let args = ["--workspace", "--all-members", "--format", "plain"];
+        assert_eq!(
+            run(&project.args(&args)).0,
+            "x 1.0.0 1.1.0 2.0.0\ny 1.0.0 1.1.0 2.0.0\nz 1.0.0 1.1.0 2.0.0\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn no_aggregate_reports_each_member_separately() {
+        let project = workspace(
+            "[workspace]\nmembers = [\"a\", \"b\"]\n",
+            &[("a", &["x", "y"]), ("b", &["y"])],
+        );
+        let args = ["--workspace", "--format", "plain"];
+        assert_eq!(
+            run(&project.args(&args)).0,
+            "x 1.0.0 1.1.0 2.0.0\ny 1.0.0 1.1.0 2.0.0\n"
+        );
+
+        let args = ["--workspace", "--no-aggregate", "--format", "plain"];
+        let report = run(&project.args(&args)).0;
+        // The headers are colored, like the table's warnings
+        let sections: Vec<_> = report.split("\n\n").collect();
+        assert_eq!(sections.len(), 5);
+        assert!(sections[0].contains('a'));
+        assert_eq!(sections[1], "x 1.0.0 1.1.0 2.0.0\ny 1.0.0 1.1.0 2.0.0");
+        assert!(sections[2].contains('b'));
+        assert_eq!(sections[3], "y 1.0.0 1.1.0 2.0.0");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn malformed_member_is_skipped() {
+        let project = workspace(
+            "[workspace]\nmembers = [\"a\", \"b\", \"c\"]\n",
+            &[("a", &["x"]), ("b", &["y"]), ("c", &["z"])],
+        );
+        project.file("b/Cargo.toml", "[package\nname = \"b\"\n");
+        let (report, code) = run(&project.args(&["--workspace", "--format", "plain"]));
+        assert_eq!(report, "x 1.0.0 1.1.0 2.0.0\nz 1.0.0 1.1.0 2.0.0\n");
+        assert_eq!(code, 0);
+        assert_eq!(project.cargo_log().len(), 4);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn workspace_without_members_is_an_error() {
+        let error = |project: &Project, args: &[&str]| {
+            let m = fixtures::matches(&project.args(args));
+            let cfg = Config::from_matches(&m).unwrap();
+            check(&cfg, &mut vec![]).unwrap_err().message().to_owned()
+        };
+        let project = workspace("[workspace]\nmembers = []\n", &[]);
+        assert!(error(&project, &["--workspace"]).starts_with("No workspace members found: "));
+        assert!(project.cargo_log().is_empty());
+
+        let project = workspace("[workspace]\nmembers = [\"a\"]\n", &[("a", &["x"])]);
+        let message = error(&project, &["--workspace", "--exclude-members", "a"]);
+        assert!(message.ends_with(" is excluded by --exclude-members"));
+        assert!(project.cargo_log().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn only_explicit_leaves_out_transitive_dependencies() {
+        let project = project();
+        let resolved = |version| {
+            let (x, t) = (format!("x {}", version), format!("t {}", version));
+            lockfile(&[("p", "0.1.0", &[&*x]), ("x", version, &[&*t]), ("t", version, &[])])
+        };
+        project.file("Cargo.lock", &resolved("1.0.0"));
+        project.cargo(&resolved("1.1.0"), &resolved("2.0.0"));
+
+        let (report, _) = run(&project.args(&["--format", "plain"]));
+        assert_eq!(report, "x 1.0.0 1.1.0 2.0.0\nx->t 1.0.0 1.1.0 2.0.0\n");
+        let (report, _) = run(&project.args(&["--only-explicit", "--format", "plain"]));
+        assert_eq!(report, "x 1.0.0 1.1.0 2.0.0\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resumed_run_only_checks_the_members_left() {
+        let project = workspace(
+            "[workspace]\nmembers = [\"a\", \"b\", \"c\"]\n",
+            &[("a", &["x"]), ("b", &["y"]), ("c", &["z"])],
+        );
+        // b can't be resolved until the `interrupt` file is removed
+        let interrupt = project.file("interrupt", "");
+        let resolve = project.path("fake-cargo/resolve");
+        fs::rename(project.path("fake-cargo/cargo"), &resolve).unwrap();
+        project.script(
+            "fake-cargo/cargo",
+            &format!(
+                "if [ -e '{}' ] && grep -qF 'name = \"b\"' \"$3\"; then\n    exit 1\nfi\n\
+                 exec '{}' \"$@\"\n",
+                interrupt.display(),
+                resolve.display()
+            ),
+        );
+        let args = project.args(&["--workspace", "--resume", "--keep-going", "--format", "plain"]);
+        let m = fixtures::matches(&args);
+        let cfg = Config::from_matches(&m).unwrap();
+        assert!(check(&cfg, &mut vec![]).is_err());
+        assert_eq!(project.cargo_log().len(), 4);
+
+        // Only b is resolved again, the results of a and c were saved by the interrupted run
+        fs::remove_file(interrupt).unwrap();
+        assert_eq!(
+            run(&args),
+            (
+                "x 1.0.0 1.1.0 2.0.0\ny 1.0.0 1.1.0 2.0.0\nz 1.0.0 1.1.0 2.0.0\n".to_owned(),
+                0
+            )
+        );
+        assert_eq!(project.cargo_log().len(), 6);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn package_and_only_kind_both_apply() {
+        let project = Project::new();
+        project.file(
+            "Cargo.toml",
+            "[package]\nname = \"p\"\nversion = \"0.1.0\"\n\
+             [dependencies]\na = \"1\"\n\
+             [dev-dependencies]\nb = \"1\"\nd = \"1\"\n",
+        );
+        let packages = |version: &'static str| {
+            let deps: Vec<_> = ["a", "b", "d"]
+                .iter()
+                .map(|d| format!("{} {}", d, version))
+                .collect();
+            let deps: Vec<_> = deps.iter().map(|d| &**d).collect();
+            lockfile(&[
+                ("p", "0.1.0", &deps),
+                ("a", version, &[]),
+                ("b", version, &[]),
+                ("d", version, &[]),
+            ])
+        };
+        project.file("Cargo.lock", &packages("1.0.0"));
+        project.cargo(&packages("1.0.0"), &packages("2.0.0"));
+        let report = |args: &[&str]| {
+            let mut args = args.to_vec();
+            args.extend(&["--format", "plain"]);
+            run(&project.args(&args)).0
+        };
+
+        assert_eq!(report(&["--only-kind", "dev"]), "b 1.0.0 -- 2.0.0\nd 1.0.0 -- 2.0.0\n");
+        assert_eq!(report(&["--package", "b", "--only-kind", "dev"]), "b 1.0.0 -- 2.0.0\n");
+        assert_eq!(report(&["--package", "a", "--only-kind", "dev"]), "");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn summary_only_has_no_dependency_rows() {
+        let project = outdated_project();
+        let (report, code) = run(&project.args(&["--summary-only", "--format", "plain"]));
+        assert_eq!(report, "total 1\ncompat 1\nlatest 1\nmajor 1\nminor 0\npatch 0\n");
+        assert_eq!(code, 0);
+
+        let (report, _) = run(&project.args(&["--summary-only", "--format", "json"]));
+        assert!(report.starts_with("{\"summary\": {\"total\": 1,"));
+        assert!(!report.contains("dependencies"));
+
+        let (report, _) = run(&project.args(&["--summary-only"]));
+        assert!(report.contains("dependencies have newer versions"));
+        assert!(!report.contains("x "));
+        assert!(!report.contains("Name"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn checksums_are_only_shown_for_registry_dependencies() {
+        let project = Project::new();
+        project.file(
+            "Cargo.toml",
+            "[package]\nname = \"p\"\nversion = \"0.1.0\"\n[dependencies]\nr = \"1\"\n\
+             l = { path = \"l\" }\ng = { git = \"https://example.com/g\" }\n",
+        );
+        let git = "git+https://example.com/g";
+        let resolved = |version: &str, rev: &str| {
+            format!(
+                "[root]\nname = \"p\"\nversion = \"0.1.0\"\n\
+                 dependencies = [\"r {v}\", \"l {v}\", \"g {v}\"]\n\n\
+                 [[package]]\nname = \"r\"\nversion = \"{v}\"\nsource = \"{crates_io}\"\n\n\
+                 [[package]]\nname = \"l\"\nversion = \"{v}\"\n\n\
+                 [[package]]\nname = \"g\"\nversion = \"{v}\"\nsource = \"{git}#{rev}\"\n\n\
+                 [metadata]\n\
+                 \"checksum r {v} ({crates_io})\" = \"sum-of-r-{v}\"\n\
+                 \"checksum g {v} ({git}#{rev})\" = \"<none>\"\n",
+                v = version,
+                rev = rev,
+                git = git,
+                crates_io = fixtures::CRATES_IO
+            )
+        };
+        project.file("Cargo.lock", &resolved("1.0.0", "aaa"));
+        project.cargo(&resolved("1.0.0", "aaa"), &resolved("2.0.0", "bbb"));
+
+        let (report, _) = run(&project.args(&["--checksums", "--format", "plain"]));
+        assert_eq!(
+            report,
+            "g 1.0.0 -- 2.0.0 --\nl 1.0.0 -- 2.0.0 --\nr 1.0.0 -- 2.0.0 sum-of-r-1.0.0\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn members_checked_in_parallel_are_merged() {
+        let project = workspace(
+            "[workspace]\nmembers = [\"a\", \"b\", \"c\", \"d\"]\n",
+            &[("a", &["x", "y"]), ("b", &["y", "z"]), ("c", &["x"]), ("d", &["w"])],
+        );
+        let serial = run(&project.args(&["--workspace", "--format", "plain"]));
+        let parallel = run(&project.args(&["--workspace", "--format", "plain", "-j", "3"]));
+        // Shared dependencies are only reported once
+        assert_eq!(
+            parallel.0,
+            "w 1.0.0 1.1.0 2.0.0\nx 1.0.0 1.1.0 2.0.0\ny 1.0.0 1.1.0 2.0.0\nz 1.0.0 1.1.0 2.0.0\n"
+        );
+        assert_eq!(parallel, serial);
+        // Each run resolved every member twice, each in its own temporary project
+        let log = project.cargo_log();
+        assert_eq!(log.len(), 16);
+        let mut manifests: Vec<_> = log.iter().map(|l| l.split(' ').nth(3).unwrap()).collect();
+        manifests.sort();
+        manifests.dedup();
+        assert_eq!(manifests.len(), 8);
+    }
+}
@@ -0,0 +1,1086 @@
+//! Renders the dependencies found by `DependencyTree::compare_versions`
+
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use tabwriter::TabWriter;
+
+use cargo_files::{DependencyKind, DependencyStatus, Update};
+use config::{Config, DependencyFormat, OutputFormat, SortOrder};
+use error::CliResult;
+use fmt::Format;
+use util;
+use version::Severity;
+
+/// Prints the dependencies in the format chosen with `--format`
+///
+/// `all_checked` is false when some workspace members couldn't be checked, so an empty list
+/// doesn't mean everything is up to date.
+pub fn print_statuses(
+    out: &mut dyn Write,
+    statuses: &[DependencyStatus],
+    all_checked: bool,
+    cfg: &Config,
+) -> CliResult<()> {
+    print_statuses_as(out, statuses, all_checked, cfg.format, cfg)
+}
+
+/// Writes the dependencies to a file in `dir` for each of `--formats`, returning the files
+pub fn write_reports(
+    dir: &Path,
+    statuses: &[DependencyStatus],
+    all_checked: bool,
+    cfg: &Config,
+) -> CliResult<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let mut written = vec![];
+    for &format in &cfg.formats {
+        let path = dir.join(match format {
+            OutputFormat::Table => "outdated.txt",
+            OutputFormat::Plain => "outdated-plain.txt",
+            OutputFormat::Html => "outdated.html",
+            OutputFormat::Sarif => "outdated.sarif",
+            OutputFormat::Json => "outdated.json",
+            OutputFormat::Markdown => "outdated.md",
+        });
+        let mut file = File::create(&path)?;
+        print_statuses_as(&mut file, statuses, all_checked, format, cfg)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+fn print_statuses_as(
+    out: &mut dyn Write,
+    statuses: &[DependencyStatus],
+    all_checked: bool,
+    format: OutputFormat,
+    cfg: &Config,
+) -> CliResult<()> {
+    let sections = match format {
+        OutputFormat::Table | OutputFormat::Plain | OutputFormat::Markdown => true,
+        OutputFormat::Html | OutputFormat::Sarif | OutputFormat::Json => false,
+    };
+    let (statuses, removed) = if cfg.concise_rm && sections && !cfg.summary_only {
+        split_removed(statuses)
+    } else {
+        (statuses.to_vec(), vec![])
+    };
+    let statuses = &statuses[..];
+    if format == OutputFormat::Html {
+        print_html(out, statuses, cfg)?;
+    } else if format == OutputFormat::Sarif {
+        print_sarif(out, statuses, cfg)?;
+    } else if format == OutputFormat::Json {
+        print_json(out, statuses, cfg)?;
+    } else if cfg.summary_only {
+        print_summary(out, statuses, format)?;
+    } else if statuses.is_empty() {
+        if all_checked && removed.is_empty() {
+            match format {
+                OutputFormat::Table => writeln!(
+                    out,
+                    "{}",
+                    Format::Good("All dependencies are up to date, yay!")
+                )?,
+                OutputFormat::Markdown => {
+                    writeln!(out, "All dependencies are up to date, yay!")?
+                }
+                _ => (),
+            }
+        }
+    } else if cfg.group_by_kind {
+        for kind in &[
+            DependencyKind::Normal,
+            DependencyKind::Development,
+            DependencyKind::Build,
+        ] {
+            let section: Vec<_> = statuses.iter().filter(|s| s.kind == *kind).collect();
+            if section.is_empty() {
+                continue;
+            }
+            if format == OutputFormat::Markdown {
+                writeln!(out, "### {}\n", kind.heading())?;
+            } else {
+                writeln!(out, "{}\n", kind.heading())?;
+            }
+            print_table(out, &section, format, cfg)?;
+            writeln!(out)?;
+        }
+    } else {
+        print_table(out, &statuses.iter().collect::<Vec<_>>(), format, cfg)?;
+    }
+    if !removed.is_empty() {
+        if format == OutputFormat::Plain {
+            writeln!(out, "removed {}", removed.join(" "))?;
+        } else {
+            if !statuses.is_empty() {
+                writeln!(out)?;
+            }
+            writeln!(out, "Removed in the latest versions: {}", removed.join(", "))?;
+        }
+    }
+    Ok(())
+}
+
+/// Splits off the dependencies which are only removed in the latest graph, returning the others
+/// along with the sorted names of the removed ones
+///
+/// Dependencies which also have a SemVer compatible update are kept, since that's worth taking.
+fn split_removed(statuses: &[DependencyStatus]) -> (Vec<DependencyStatus>, Vec<String>) {
+    let (removed, kept): (Vec<_>, Vec<_>) = statuses.iter().cloned().partition(|s| {
+        s.latest == Update::Removed &&
+            !matches!(s.compat, Update::Version(_) | Update::SourceChanged(..))
+    });
+    let mut names: Vec<_> = removed.into_iter().map(|s| s.name).collect();
+    names.sort();
+    names.dedup();
+    (kept, names)
+}
+
+/// The columns shown for a dependency in the table and plain formats
+fn row_fields(status: &DependencyStatus, format: DependencyFormat) -> Vec<String> {
+    let update = |u: &Update| match (u, &status.requirement) {
+        (&Update::Unchanged, _) => "--".to_owned(),
+        (Update::Version(v), Some(req)) if format == DependencyFormat::Requirement => {
+            format!("{} -> {}", req, v)
+        }
+        (Update::Version(v), _) => v.clone(),
+        (Update::SourceChanged(v, source), _) => format!("{} from {}", v, source),
+        (&Update::Removed, _) => "RM".to_owned(),
+        (&Update::Patched, _) => "patched".to_owned(),
+    };
+    let project = match status.source {
+        Some(ref source) if format == DependencyFormat::Source => {
+            // Drop the commit of git sources, it's already implied by the version
+            format!("{} ({})", status.project, source.split('#').next().unwrap_or(source))
+        }
+        None if format == DependencyFormat::Source => format!("{} (path)", status.project),
+        _ => status.project.clone(),
+    };
+    vec![
+        status.display_name(),
+        status
+            .requirement
+            .clone()
+            .unwrap_or_else(|| "--".to_owned()),
+        project,
+        update(&status.compat),
+        update(&status.latest),
+    ]
+}
+
+/// The headers of the optional columns added after the latest version
+fn extra_headers(cfg: &Config) -> Vec<&'static str> {
+    let mut headers = vec![];
+    if cfg.checksums {
+        headers.push("Checksum");
+    }
+    if cfg.show_kind {
+        headers.push("Kind");
+    }
+    if cfg.links {
+        headers.push("Link");
+    }
+    headers
+}
+
+/// Where rows with an update of `severity` are placed, before rows with a higher rank
+///
+/// Sorting by severity puts the most severe first and rows without a parseable version last.
+/// Rows of the same rank are sorted by name.
+fn sort_rank(order: SortOrder, severity: Option<Severity>) -> u8 {
+    match (order, severity) {
+        (SortOrder::Name, _) => 0,
+        (SortOrder::Severity, Some(Severity::Major)) => 0,
+        (SortOrder::Severity, Some(Severity::Minor)) => 1,
+        (SortOrder::Severity, Some(Severity::Patch)) => 2,
+        (SortOrder::Severity, None) => 3,
+    }
+}
+
+/// The rows of a table in display order, along with the severity of each row's update
+fn sorted_rows(
+    statuses: &[&DependencyStatus],
+    cfg: &Config,
+) -> Vec<(Option<Severity>, Vec<String>)> {
+    let mut rows: Vec<_> = statuses
+        .iter()
+        .map(|s| {
+            let rank = sort_rank(cfg.sort, s.severity());
+            let mut fields = row_fields(s, cfg.dependency_format);
+            if cfg.collapse && s.latest == s.compat {
+                fields[4] = "--".to_owned();
+            }
+            if cfg.checksums {
+                fields.push(s.checksum.clone().unwrap_or_else(|| "--".to_owned()));
+            }
+            if cfg.show_kind {
+                fields.push(s.kind.as_str().to_owned());
+            }
+            if cfg.links {
+                let link = match s.source {
+                    Some(_) => util::package_link(&s.name, &s.project),
+                    None => None,
+                };
+                fields.push(link.unwrap_or_else(|| "--".to_owned()));
+            }
+            (rank, fields, s.severity())
+        })
+        .collect();
+    rows.sort();
+    // The same dependency may be reached through more than one kind of dependency
+    rows.dedup();
+    rows.into_iter().map(|(_, row, severity)| (severity, row)).collect()
+}
+
+fn print_table(
+    out: &mut dyn Write,
+    statuses: &[&DependencyStatus],
+    format: OutputFormat,
+    cfg: &Config,
+) -> CliResult<()> {
+    let rows: Vec<_> = sorted_rows(statuses, cfg)
+        .into_iter()
+        .map(|(_, row)| row)
+        .collect();
+
+    if format == OutputFormat::Plain {
+        // The requirement is left out, it's in Cargo.toml already
+        for row in rows {
+            let fields: Vec<_> = row
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| i != 1)
+                .map(|(_, f)| &**f)
+                .collect();
+            writeln!(out, "{}", fields.join(" "))?;
+        }
+        return Ok(());
+    } else if format == OutputFormat::Markdown {
+        return print_markdown(out, &rows, cfg);
+    }
+    let pad = |f: &str| if f == "--" || f == "RM" {
+        format!("  {}  ", f)
+    } else {
+        f.to_owned()
+    };
+    let mut tw = TabWriter::new(vec![]);
+    write!(&mut tw, "Name\tRequirement\tProject Ver\tSemVer Compat\tLatest Ver")
+        .unwrap_or_else(|e| panic!("write! error: {}", e));
+    for header in extra_headers(cfg) {
+        write!(&mut tw, "\t{}", header).unwrap_or_else(|e| panic!("write! error: {}", e));
+    }
+    writeln!(&mut tw).unwrap_or_else(|e| panic!("write! error: {}", e));
+    for row in rows {
+        write!(
+            &mut tw,
+            "{}\t   {}\t   {}\t   {}\t  {}",
+            row[0],
+            pad(&row[1]),
+            row[2],
+            pad(&row[3]),
+            pad(&row[4])
+        )?;
+        for field in &row[5..] {
+            write!(&mut tw, "\t  {}", pad(field))?;
+        }
+        writeln!(&mut tw)?;
+    }
+    tw.flush()
+        .unwrap_or_else(|e| panic!("failed to flush TabWriter: {}", e));
+    write!(
+        out,
+        "{}",
+        String::from_utf8(tw.into_inner().unwrap())
+            .unwrap_or_else(|e| panic!("from_utf8 error: {}", e))
+    )?;
+    Ok(())
+}
+
+/// Writes the rows as a GitHub flavored Markdown table
+fn print_markdown(out: &mut dyn Write, rows: &[Vec<String>], cfg: &Config) -> CliResult<()> {
+    let mut headers = vec![
+        "Name",
+        "Requirement",
+        "Project Ver",
+        "SemVer Compat",
+        "Latest Ver",
+    ];
+    headers.extend(extra_headers(cfg));
+    writeln!(out, "| {} |", headers.join(" | "))?;
+    writeln!(out, "|{}", "---|".repeat(headers.len()))?;
+    for row in rows {
+        let cells: Vec<_> = row.iter().map(|f| escape_markdown(f)).collect();
+        writeln!(out, "| {} |", cells.join(" | "))?;
+    }
+    Ok(())
+}
+
+/// Writes a self-contained HTML report, with the summary counts above a table of the
+/// dependencies colored by severity
+fn print_html(out: &mut dyn Write, statuses: &[DependencyStatus], cfg: &Config) -> CliResult<()> {
+    let summary = Summary::new(statuses);
+    let mut html = String::from(
+        "<!DOCTYPE html>\n\
+         <html>\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <title>Outdated dependencies</title>\n\
+         <style>\n\
+         body { font-family: sans-serif; }\n\
+         table { border-collapse: collapse; }\n\
+         th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; }\n\
+         tr.major { background: #f8d7da; }\n\
+         tr.minor { background: #fff3cd; }\n\
+         tr.patch { background: #d4edda; }\n\
+         </style>\n\
+         </head>\n\
+         <body>\n\
+         <h1>Outdated dependencies</h1>\n",
+    );
+    html.push_str(&format!(
+        "<p>{} dependencies have newer versions: {} SemVer compatible, {} latest \
+         ({} major, {} minor, {} patch)</p>\n",
+        summary.total,
+        summary.compat,
+        summary.latest,
+        summary.major,
+        summary.minor,
+        summary.patch
+    ));
+    if !cfg.summary_only && !statuses.is_empty() {
+        let mut headers = vec![
+            "Name",
+            "Requirement",
+            "Project Ver",
+            "SemVer Compat",
+            "Latest Ver",
+        ];
+        headers.extend(extra_headers(cfg));
+        html.push_str("<table>\n<tr>");
+        for header in headers {
+            html.push_str(&format!("<th>{}</th>", header));
+        }
+        html.push_str("</tr>\n");
+        for (severity, row) in sorted_rows(&statuses.iter().collect::<Vec<_>>(), cfg) {
+            html.push_str(&format!(
+                "<tr class=\"{}\">",
+                severity.map(|s| s.as_str()).unwrap_or("unknown")
+            ));
+            for field in row {
+                html.push_str(&format!("<td>{}</td>", escape_html(&field)));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+    }
+    html.push_str("</body>\n</html>");
+    writeln!(out, "{}", html)?;
+    Ok(())
+}
+
+/// Writes a SARIF 2.1.0 log with a result per dependency, located at the checked manifest
+///
+/// Each severity is its own rule, so code scanning can filter or dismiss them separately.
+/// Updates without a severity, e.g. a removed dependency, use the `outdated` rule. When the
+/// requirement of a direct dependency doesn't allow its latest version, the requirement which
+/// would is added as the `suggestedRequirement` property, so a bot can edit the manifest.
+fn print_sarif(out: &mut dyn Write, statuses: &[DependencyStatus], cfg: &Config) -> CliResult<()> {
+    let manifest = env::current_dir()
+        .ok()
+        .and_then(|cwd| cfg.manifest.strip_prefix(cwd).ok().map(|p| p.to_path_buf()))
+        .unwrap_or_else(|| cfg.manifest.clone());
+    let uri = manifest.to_string_lossy().replace('\\', "/");
+
+    let rules: Vec<_> = [
+        ("outdated-major", "A dependency has a newer major version"),
+        ("outdated-minor", "A dependency has a newer minor version"),
+        ("outdated-patch", "A dependency has a newer patch version"),
+        ("outdated", "A dependency was removed or resolved from another source"),
+    ].iter()
+        .map(|&(id, description)| {
+            format!(
+                "{{\"id\": \"{}\", \"shortDescription\": {{\"text\": \"{}\"}}}}",
+                id,
+                description
+            )
+        })
+        .collect();
+    let mut results: Vec<_> = statuses
+        .iter()
+        .map(|s| {
+            let row = row_fields(s, cfg.dependency_format);
+            let (rule, level) = match s.severity() {
+                Some(Severity::Major) => ("outdated-major", "warning"),
+                Some(Severity::Minor) => ("outdated-minor", "note"),
+                Some(Severity::Patch) => ("outdated-patch", "note"),
+                None => ("outdated", "note"),
+            };
+            let message = format!(
+                "{} {} is outdated (SemVer compatible: {}, latest: {})",
+                row[0],
+                row[2],
+                row[3],
+                row[4]
+            );
+            let properties = match s.suggested_requirement() {
+                Some(suggested) => format!(
+                    ", \"properties\": {{\"requirement\": \"{}\", \
+                     \"suggestedRequirement\": \"{}\"}}",
+                    escape_json(s.requirement.as_ref().map_or("", |r| &**r)),
+                    escape_json(&suggested)
+                ),
+                None => String::new(),
+            };
+            format!(
+                "{{\"ruleId\": \"{}\", \"level\": \"{}\", \"message\": {{\"text\": \"{}\"}}, \
+                 \"locations\": [{{\"physicalLocation\": {{\"artifactLocation\": \
+                 {{\"uri\": \"{}\"}}}}}}]{}}}",
+                rule,
+                level,
+                escape_json(&message),
+                escape_json(&uri),
+                properties
+            )
+        })
+        .collect();
+    // The same dependency may be reached through more than one kind of dependency
+    results.sort();
+    results.dedup();
+
+    writeln!(
+        out,
+        "{{\"$schema\": \"https://json.schemastore.org/sarif-2.1.0.json\", \"version\": \"2.1.0\", \
+         \"runs\": [{{\"tool\": {{\"driver\": {{\"name\": \"cargo-outdated\", \"version\": \"{}\", \
+         \"informationUri\": \"https://github.com/kbknapp/cargo-outdated\", \"rules\": [{}]}}}}, \
+         \"results\": [{}]}}]}}",
+        env!("CARGO_PKG_VERSION"),
+        rules.join(", "),
+        results.join(", ")
+    )?;
+    Ok(())
+}
+
+/// Writes a JSON object with the summary counts and, unless `--summary-only` is given, a
+/// `dependencies` array with an object per dependency
+///
+/// `compat` and `latest` are the newer version, or `null` when there is none. A dependency
+/// which is gone from either graph has `removed` set, and one replaced by a `[patch]` has
+/// `patched` set. `suggestedRequirement` is the requirement which would allow the latest
+/// version, when the manifest's doesn't, so a bot can edit the manifest. With
+/// `--json-compact-nulls` the keys whose value is `null` are left out.
+fn print_json(out: &mut dyn Write, statuses: &[DependencyStatus], cfg: &Config) -> CliResult<()> {
+    let summary = Summary::new(statuses);
+    let summary = format!(
+        "\"summary\": {{\"total\": {}, \"compat\": {}, \"latest\": {}, \"major\": {}, \
+         \"minor\": {}, \"patch\": {}}}",
+        summary.total,
+        summary.compat,
+        summary.latest,
+        summary.major,
+        summary.minor,
+        summary.patch
+    );
+    if cfg.summary_only {
+        writeln!(out, "{{{}}}", summary)?;
+        return Ok(());
+    }
+
+    let string = |s: Option<&str>| match s {
+        Some(s) => format!("\"{}\"", escape_json(s)),
+        None => "null".to_owned(),
+    };
+    let version = |u: &Update| match *u {
+        Update::Version(ref v) | Update::SourceChanged(ref v, _) => string(Some(v)),
+        Update::Unchanged | Update::Removed | Update::Patched => "null".to_owned(),
+    };
+    let mut dependencies: Vec<_> = statuses
+        .iter()
+        .map(|s| {
+            let fields = vec![
+                ("name", string(Some(&s.name))),
+                ("parent", string(s.parent.as_deref())),
+                ("kind", string(Some(s.kind.as_str()))),
+                ("requirement", string(s.requirement.as_deref())),
+                ("project", string(Some(&s.project))),
+                ("source", string(s.source.as_deref())),
+                ("compat", version(&s.compat)),
+                ("latest", version(&s.latest)),
+                (
+                    "removed",
+                    (s.compat == Update::Removed || s.latest == Update::Removed).to_string(),
+                ),
+                (
+                    "patched",
+                    (s.compat == Update::Patched || s.latest == Update::Patched).to_string(),
+                ),
+                ("severity", string(s.severity().map(|s| s.as_str()))),
+                ("suggestedRequirement", string(s.suggested_requirement().as_deref())),
+            ];
+            let fields: Vec<_> = fields
+                .into_iter()
+                .filter(|(_, value)| !cfg.json_compact_nulls || value != "null")
+                .map(|(key, value)| format!("\"{}\": {}", key, value))
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        })
+        .collect();
+    // The same dependency may be reached through more than one kind of dependency
+    dependencies.sort();
+    dependencies.dedup();
+
+    writeln!(
+        out,
+        "{{{}, \"dependencies\": [{}]}}",
+        summary,
+        dependencies.join(", ")
+    )?;
+    Ok(())
+}
+
+/// Escapes a string for use inside a JSON string literal
+fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escapes the characters which would end a cell of a Markdown table
+fn escape_markdown(s: &str) -> String {
+    s.replace('|', "\\|")
+}
+
+/// Escapes the characters with a special meaning in HTML text and attribute values
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Counts of the dependencies with newer versions, each dependency counted once
+#[derive(Debug, Default)]
+struct Summary {
+    total: usize,
+    compat: usize,
+    latest: usize,
+    major: usize,
+    minor: usize,
+    patch: usize,
+}
+
+impl Summary {
+    fn new(statuses: &[DependencyStatus]) -> Summary {
+        let mut rows: Vec<_> = statuses
+            .iter()
+            .map(|s| (row_fields(s, DependencyFormat::Bare), s))
+            .collect();
+        rows.sort_by(|a, b| a.0.cmp(&b.0));
+        rows.dedup_by(|a, b| a.0 == b.0);
+
+        let mut summary = Summary::default();
+        for (_, status) in rows {
+            summary.total += 1;
+            if let Update::Version(_) = status.compat {
+                summary.compat += 1;
+            }
+            if let Update::Version(_) = status.latest {
+                summary.latest += 1;
+            }
+            match status.severity() {
+                Some(Severity::Major) => summary.major += 1,
+                Some(Severity::Minor) => summary.minor += 1,
+                Some(Severity::Patch) => summary.patch += 1,
+                None => (),
+            }
+        }
+        summary
+    }
+}
+
+/// Why the process exits with `code`, so CI logs show what failed the build
+pub fn exit_reason(code: i32, statuses: &[DependencyStatus]) -> String {
+    let summary = Summary::new(statuses);
+    format!(
+        "exiting {}: {} dependencies have newer versions ({} major, {} minor, {} patch)",
+        code,
+        summary.total,
+        summary.major,
+        summary.minor,
+        summary.patch
+    )
+}
+
+/// Writes a shields.io endpoint badge with the number of outdated dependencies, colored by the
+/// most severe update
+pub fn write_badge(path: &Path, statuses: &[DependencyStatus]) -> CliResult<()> {
+    let summary = Summary::new(statuses);
+    let (message, color) = if summary.total == 0 {
+        ("up to date".to_owned(), "brightgreen")
+    } else {
+        let color = if summary.major > 0 {
+            "red"
+        } else if summary.minor > 0 {
+            "yellow"
+        } else if summary.patch > 0 {
+            "yellowgreen"
+        } else {
+            "lightgrey"
+        };
+        (format!("{} outdated", summary.total), color)
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = File::create(path)?;
+    writeln!(
+        file,
+        "{{\"schemaVersion\": 1, \"label\": \"dependencies\", \"message\": \"{}\", \
+         \"color\": \"{}\"}}",
+        message,
+        color
+    )?;
+    Ok(())
+}
+
+fn print_summary(
+    out: &mut dyn Write,
+    statuses: &[DependencyStatus],
+    format: OutputFormat,
+) -> CliResult<()> {
+    let summary = Summary::new(statuses);
+    match format {
+        OutputFormat::Plain => {
+            writeln!(out, "total {}", summary.total)?;
+            writeln!(out, "compat {}", summary.compat)?;
+            writeln!(out, "latest {}", summary.latest)?;
+            writeln!(out, "major {}", summary.major)?;
+            writeln!(out, "minor {}", summary.minor)?;
+            writeln!(out, "patch {}", summary.patch)?;
+        }
+        OutputFormat::Table |
+        OutputFormat::Html |
+        OutputFormat::Sarif |
+        OutputFormat::Json |
+        OutputFormat::Markdown => {
+            // Nothing to do is good, major updates may break the build, anything else is a warning
+            let count = |n: usize, breaking: bool| match n {
+                0 => Format::Good(n.to_string()),
+                _ if breaking => Format::Error(n.to_string()),
+                _ => Format::Warning(n.to_string()),
+            };
+            writeln!(
+                out,
+                "{} dependencies have newer versions: {} SemVer compatible, {} latest \
+                 ({} major, {} minor, {} patch)",
+                count(summary.total, false),
+                count(summary.compat, false),
+                count(summary.latest, false),
+                count(summary.major, true),
+                count(summary.minor, false),
+                count(summary.patch, false)
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints how each outdated transitive dependency is reached from the root
+pub fn print_paths(out: &mut dyn Write, statuses: &[DependencyStatus]) -> CliResult<()> {
+    let mut paths: Vec<_> = statuses
+        .iter()
+        .filter(|s| s.parent.is_some() && !s.path.is_empty())
+        .map(|s| format!("{}: {}", s.name, s.path.join(" -> ")))
+        .collect();
+    paths.sort();
+    paths.dedup();
+    if paths.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "\nTransitive dependencies are reached through:\n")?;
+    for path in paths {
+        writeln!(out, "    {}", path)?;
+    }
+    Ok(())
+}
+
+/// Prints the dependencies found by `unused_dependencies`
+pub fn print_unused(
+    out: &mut dyn Write,
+    unused: &[(String, DependencyKind, String, &str)],
+) -> CliResult<()> {
+    if unused.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "\nDeclared dependencies which may be unused:\n")?;
+    for &(ref package, kind, ref dep_name, reason) in unused {
+        writeln!(out, "    {} [{}] {}: {}", package, kind.table_name(), dep_name, reason)?;
+    }
+    Ok(())
+}
+
+/// Prints the dependencies whose requirement differs from the one in the manifest passed to
+/// `--against`, as `(name, requirement, other requirement)`
+pub fn print_divergences(
+    out: &mut dyn Write,
+    against: &Path,
+    divergences: &[(String, String, String)],
+) -> CliResult<()> {
+    if divergences.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "\nRequirements which differ from {}:\n", against.display())?;
+    let mut tw = TabWriter::new(vec![]);
+    writeln!(&mut tw, "Name\tRequirement\tAgainst")?;
+    for (name, requirement, other) in divergences {
+        writeln!(&mut tw, "{}\t{}\t{}", name, requirement, other)?;
+    }
+    tw.flush()?;
+    write!(out, "{}", String::from_utf8(tw.into_inner().unwrap()).unwrap())?;
+    Ok(())
+}
+
+/// Prints how to take each update available to a direct dependency
+///
+/// SemVer compatible updates only need the lockfile updated, anything newer needs the requirement
+/// in the manifest changed.
+pub fn print_suggestions(out: &mut dyn Write, statuses: &[DependencyStatus]) -> CliResult<()> {
+    let mut suggestions = vec![];
+    for status in statuses.iter().filter(|s| s.parent.is_none()) {
+        // The version is given as the lockfile may hold several versions of the package
+        if let Update::Version(ref compat) = status.compat {
+            suggestions.push(format!(
+                "cargo update -p {}:{} --precise {}",
+                status.name,
+                status.project,
+                compat
+            ));
+        }
+        if let Update::Version(ref latest) = status.latest {
+            if status.latest != status.compat {
+                let breaking = Severity::between(&status.project, latest) == Some(Severity::Major);
+                suggestions.push(format!(
+                    "edit Cargo.toml: {} = \"{}\"{}",
+                    status.name,
+                    latest,
+                    if breaking { " (breaking change)" } else { "" }
+                ));
+            }
+        }
+    }
+    suggestions.sort();
+    suggestions.dedup();
+    if suggestions.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "\nTo update direct dependencies:\n")?;
+    for suggestion in suggestions {
+        writeln!(out, "    {}", suggestion)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use fixtures::{self, Project};
+
+    use super::*;
+
+    fn status(
+        name: &str,
+        requirement: &str,
+        project: &str,
+        compat: Update,
+        latest: Update,
+    ) -> DependencyStatus {
+        DependencyStatus {
+            kind: DependencyKind::Normal,
+            parent: None,
+            name: name.to_owned(),
+            requirement: Some(requirement.to_owned()),
+            project: project.to_owned(),
+            checksum: None,
+            source: Some("registry+https://github.com/rust-lang/crates.io-index".to_owned()),
+            compat,
+            latest,
+            path: vec![],
+        }
+    }
+
+    fn version(v: &str) -> Update {
+        Update::Version(v.to_owned())
+    }
+
+    /// The report of `statuses` with `args`
+    fn render(statuses: &[DependencyStatus], args: &[&str]) -> String {
+        let project = Project::new();
+        project.file("Cargo.toml", "[package]\nname = \"p\"\nversion = \"0.1.0\"\n");
+        project.file("Cargo.lock", "");
+        let m = fixtures::matches(&project.args(args));
+        let cfg = Config::from_matches(&m).unwrap();
+        let mut out = vec![];
+        print_statuses(&mut out, statuses, true, &cfg).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn plain_rows_are_space_separated() {
+        let x = status("x", "~1.0", "1.0.0", Update::Unchanged, version("2.0.0"));
+        let mut y = status("y", "1", "1.0.0", version("1.0.1"), Update::Patched);
+        y.parent = Some("x".to_owned());
+        let report = render(&[x, y], &["--format", "plain", "--color", "always"]);
+        assert_eq!(report, "x 1.0.0 -- 2.0.0\nx->y 1.0.0 1.0.1 patched\n");
+        assert!(!report.contains("\x1b["));
+    }
+
+    #[test]
+    fn collapse_only_hides_a_latest_version_equal_to_the_compatible_one() {
+        let x = status("x", "1.0", "1.0.0", version("1.2.0"), version("2.0.0"));
+        let y = status("y", "1.0", "1.0.0", version("1.0.1"), version("1.0.1"));
+        let statuses = [x, y];
+        assert_eq!(
+            render(&statuses, &["--format", "plain"]),
+            "x 1.0.0 1.2.0 2.0.0\ny 1.0.0 1.0.1 1.0.1\n"
+        );
+        assert_eq!(
+            render(&statuses, &["--format", "plain", "--collapse"]),
+            "x 1.0.0 1.2.0 2.0.0\ny 1.0.0 1.0.1 --\n"
+        );
+    }
+
+    #[test]
+    fn concise_rm_lists_the_removed_dependencies_on_one_line() {
+        let statuses = [
+            status("x", "1.0", "1.0.0", Update::Unchanged, version("2.0.0")),
+            status("w", "1.0", "1.0.0", Update::Unchanged, Update::Removed),
+            status("v", "1.0", "1.0.0", Update::Unchanged, Update::Removed),
+            // Still worth a row for its compatible update
+            status("u", "1.0", "1.0.0", version("1.0.1"), Update::Removed),
+        ];
+        assert_eq!(
+            render(&statuses, &["--format", "plain", "--concise-rm"]),
+            "u 1.0.0 1.0.1 RM\nx 1.0.0 -- 2.0.0\nremoved v w\n"
+        );
+        let report = render(&statuses, &["--format", "markdown", "--concise-rm"]);
+        assert!(report.ends_with("\n\nRemoved in the latest versions: v, w\n"));
+        assert!(!report.contains("| w "));
+    }
+
+    fn suggestions(statuses: &[DependencyStatus]) -> String {
+        let mut out = vec![];
+        print_suggestions(&mut out, statuses).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn suggestions_name_the_version_to_update() {
+        let statuses = [status("x", "1.0", "1.0.0", version("1.0.5"), version("1.0.5"))];
+        assert!(suggestions(&statuses).contains("cargo update -p x:1.0.0 --precise 1.0.5\n"));
+        assert!(!suggestions(&statuses).contains("edit Cargo.toml"));
+    }
+
+    #[test]
+    fn only_major_updates_are_breaking() {
+        let minor = [status("x", "~1.0", "1.0.0", Update::Unchanged, version("1.2.0"))];
+        assert!(suggestions(&minor).contains("edit Cargo.toml: x = \"1.2.0\"\n"));
+        let major = [status("x", "1.0", "1.0.0", Update::Unchanged, version("2.0.0"))];
+        assert!(suggestions(&major).contains("x = \"2.0.0\" (breaking change)\n"));
+        let pre_1_0 = [status("x", "0.1", "0.1.0", Update::Unchanged, version("0.2.0"))];
+        assert!(suggestions(&pre_1_0).contains("x = \"0.2.0\" (breaking change)\n"));
+    }
+
+    #[test]
+    fn no_suggestions_for_transitive_dependencies() {
+        let mut s = status("y", "1.0", "1.0.0", version("1.0.5"), version("2.0.0"));
+        s.parent = Some("x".to_owned());
+        assert_eq!(suggestions(&[s]), "");
+    }
+
+    #[test]
+    fn severity_ranks() {
+        let mut rows = [
+            (sort_rank(SortOrder::Severity, None), "e"),
+            (sort_rank(SortOrder::Severity, Some(Severity::Patch)), "a"),
+            (sort_rank(SortOrder::Severity, Some(Severity::Major)), "d"),
+            (sort_rank(SortOrder::Severity, Some(Severity::Minor)), "b"),
+            (sort_rank(SortOrder::Severity, Some(Severity::Major)), "c"),
+        ];
+        rows.sort();
+        let names: Vec<_> = rows.iter().map(|&(_, name)| name).collect();
+        assert_eq!(names, vec!["c", "d", "b", "a", "e"]);
+    }
+
+    #[test]
+    fn name_ranks() {
+        assert_eq!(
+            sort_rank(SortOrder::Name, Some(Severity::Patch)),
+            sort_rank(SortOrder::Name, Some(Severity::Major))
+        );
+        assert_eq!(
+            sort_rank(SortOrder::Name, None),
+            sort_rank(SortOrder::Name, Some(Severity::Major))
+        );
+    }
+
+    #[test]
+    fn json_states_are_distinct() {
+        let mut unchanged = status("x", "1.0", "1.0.0", Update::Unchanged, version("1.2.0"));
+        unchanged.source = None;
+        let mut removed = status("y", "1.0", "1.0.0", Update::Removed, Update::Removed);
+        removed.source = None;
+        let statuses = [unchanged, removed];
+
+        let report = render(&statuses, &["--format", "json"]);
+        assert!(report.contains(
+            "{\"name\": \"x\", \"parent\": null, \"kind\": \"normal\", \"requirement\": \"1.0\", \
+             \"project\": \"1.0.0\", \"source\": null, \"compat\": null, \"latest\": \"1.2.0\", \
+             \"removed\": false, \"patched\": false,"
+        ));
+        assert!(report.contains(
+            "\"source\": null, \"compat\": null, \"latest\": null, \"removed\": true,"
+        ));
+
+        let compact = render(&statuses, &["--format", "json", "--json-compact-nulls"]);
+        assert!(!compact.contains("null"));
+        assert!(compact.contains(
+            "{\"name\": \"x\", \"kind\": \"normal\", \"requirement\": \"1.0\", \
+             \"project\": \"1.0.0\", \"latest\": \"1.2.0\", \"removed\": false,"
+        ));
+        assert!(compact.contains("\"project\": \"1.0.0\", \"removed\": true,"));
+    }
+
+    #[test]
+    fn sarif_has_the_required_fields() {
+        let x = status("x", "1.0", "1.0.0", Update::Unchanged, version("2.0.0"));
+        let y = status("y", "1.0", "1.0.0", version("1.0.1"), version("1.0.1"));
+        let report = render(&[x, y], &["--format", "sarif"]);
+        assert!(report.starts_with(
+            "{\"$schema\": \"https://json.schemastore.org/sarif-2.1.0.json\", \
+             \"version\": \"2.1.0\", \"runs\": [{\"tool\": {\"driver\": \
+             {\"name\": \"cargo-outdated\", \"version\": "
+        ));
+        assert!(report.contains(
+            "{\"id\": \"outdated-major\", \"shortDescription\": {\"text\": "
+        ));
+        // Each result names its rule and the manifest the dependency is declared in
+        assert!(report.contains(
+            "{\"ruleId\": \"outdated-major\", \"level\": \"warning\", \"message\": {\"text\": \
+             \"x 1.0.0 is outdated (SemVer compatible: --, latest: 2.0.0)\"}, \"locations\": \
+             [{\"physicalLocation\": {\"artifactLocation\": {\"uri\": \""
+        ));
+        assert!(report.contains(
+            "{\"ruleId\": \"outdated-patch\", \"level\": \"note\", \"message\": {\"text\": \
+             \"y 1.0.0 is outdated (SemVer compatible: 1.0.1, latest: 1.0.1)\"}, \"locations\": "
+        ));
+        assert_eq!(report.matches("/Cargo.toml\"}}}]").count(), 2);
+        assert!(report.ends_with("]}]}\n"));
+    }
+
+    #[test]
+    fn badge_counts_the_outdated_dependencies() {
+        let project = Project::new();
+        let badge = project.path("badges/outdated.json");
+        let read = || fs::read_to_string(&badge).unwrap();
+
+        write_badge(&badge, &[]).unwrap();
+        assert_eq!(
+            read(),
+            "{\"schemaVersion\": 1, \"label\": \"dependencies\", \"message\": \"up to date\", \
+             \"color\": \"brightgreen\"}\n"
+        );
+
+        let x = status("x", "1.0", "1.0.0", Update::Unchanged, version("2.0.0"));
+        let y = status("y", "1.0", "1.0.0", version("1.0.1"), version("1.0.1"));
+        write_badge(&badge, &[x, y.clone()]).unwrap();
+        assert!(read().contains("\"message\": \"2 outdated\", \"color\": \"red\""));
+        write_badge(&badge, &[y]).unwrap();
+        assert!(read().contains("\"message\": \"1 outdated\", \"color\": \"yellowgreen\""));
+    }
+
+    #[test]
+    fn exit_reason_counts_each_severity() {
+        let x = status("x", "1.0", "1.0.0", Update::Unchanged, version("2.0.0"));
+        let y = status("y", "1.0", "1.0.0", version("1.0.1"), version("1.0.1"));
+        let z = status("z", "1.0", "1.0.0", version("1.0.2"), version("1.0.2"));
+        assert_eq!(
+            exit_reason(3, &[x, y, z]),
+            "exiting 3: 3 dependencies have newer versions (1 major, 0 minor, 2 patch)"
+        );
+    }
+
+    /// Whether every element of `html` is closed in the order it was opened
+    fn is_well_formed(html: &str) -> bool {
+        let mut open = vec![];
+        for tag in html.split('<').skip(1) {
+            let tag = &tag[..tag.find('>').unwrap()];
+            let name = tag.trim_start_matches('/').split(' ').next().unwrap();
+            if tag.starts_with('!') || name == "meta" {
+                continue;
+            } else if tag.starts_with('/') {
+                if open.pop() != Some(name) {
+                    return false;
+                }
+            } else {
+                open.push(name);
+            }
+        }
+        open.is_empty()
+    }
+
+    #[test]
+    fn html_is_well_formed_with_a_row_per_dependency() {
+        let x = status("x", "1.0", "1.0.0", Update::Unchanged, version("2.0.0"));
+        let y = status("<y&z>", "1.0", "1.0.0", version("1.0.1"), version("1.0.1"));
+        let report = render(&[x, y], &["--format", "html"]);
+        assert!(report.starts_with("<!DOCTYPE html>\n<html>\n"));
+        assert!(is_well_formed(&report));
+        assert!(report.contains("<p>2 dependencies have newer versions: 1 SemVer compatible"));
+        assert!(report.contains(
+            "<tr class=\"patch\"><td>&lt;y&amp;z&gt;</td><td>1.0</td><td>1.0.0</td>\
+             <td>1.0.1</td><td>1.0.1</td></tr>\n\
+             <tr class=\"major\"><td>x</td><td>1.0</td><td>1.0.0</td>\
+             <td>--</td><td>2.0.0</td></tr>\n"
+        ));
+    }
+
+    /// The only test which changes the colors, which are on until a command line is parsed
+    #[cfg(all(feature = "color", not(target_os = "windows")))]
+    #[test]
+    fn no_color_disables_ansi_output() {
+        let colored = |args: &[&str]| {
+            let project = Project::new();
+            project.file("Cargo.toml", "[package]\nname = \"p\"\nversion = \"0.1.0\"\n");
+            project.file("Cargo.lock", "");
+            let m = fixtures::matches(&project.args(args));
+            let cfg = Config::from_matches(&m).unwrap();
+            ::fmt::set_color(cfg.color);
+            let mut out = vec![];
+            print_statuses(&mut out, &[], true, &cfg).unwrap();
+            ::fmt::set_color(true);
+            String::from_utf8(out).unwrap()
+        };
+        assert!(colored(&["--color", "always"]).contains("\x1b["));
+        assert_eq!(colored(&["--no-color"]), "All dependencies are up to date, yay!\n");
+    }
+}
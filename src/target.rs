@@ -0,0 +1,220 @@
+//! Matching `[target.'...'.dependencies]` tables against the platform given with `--target`, or
+//! else the host
+
+use std::process;
+
+use error::{CliError, CliResult};
+
+/// A platform, along with the `cfg` values `rustc` sets when compiling for it
+#[derive(Debug)]
+pub struct Target {
+    triple: String,
+    /// Each `name` or `name="value"` printed by `rustc --print cfg`
+    cfgs: Vec<(String, Option<String>)>,
+}
+
+impl Target {
+    /// Asks `rustc` for the `cfg` values of `triple`
+    pub fn new(triple: &str) -> CliResult<Target> {
+        let output = process::Command::new("rustc")
+            .arg("--print")
+            .arg("cfg")
+            .arg("--target")
+            .arg(triple)
+            .output()?;
+        if !output.status.success() {
+            return Err(CliError::Generic(format!(
+                "Failed to get the cfg values of target '{}': {}",
+                triple,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        let cfgs = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| {
+                let mut parts = line.splitn(2, '=');
+                let name = parts.next().unwrap_or("").trim().to_owned();
+                let value = parts.next().map(|v| v.trim().trim_matches('"').to_owned());
+                (name, value)
+            })
+            .collect();
+        Ok(Target {
+            triple: triple.to_owned(),
+            cfgs,
+        })
+    }
+
+    /// The platform `rustc` compiles for by default, as printed by `rustc -vV`
+    pub fn host() -> CliResult<Target> {
+        let output = process::Command::new("rustc").arg("-vV").output()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let host = stdout
+            .lines()
+            .find(|line| line.starts_with("host:"))
+            .map(|line| line["host:".len()..].trim());
+        match host {
+            Some(triple) if output.status.success() => Target::new(triple),
+            _ => Err(CliError::Generic(format!(
+                "Failed to get the host target from 'rustc -vV': {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))),
+        }
+    }
+
+    pub fn triple(&self) -> &str {
+        &self.triple
+    }
+
+    /// Whether the key of a `[target.<key>]` table applies to this target
+    ///
+    /// The key is either a target triple or a `cfg(...)` expression. Expressions which can't be
+    /// parsed don't match.
+    pub fn matches(&self, key: &str) -> bool {
+        let key = key.trim();
+        if key.starts_with("cfg(") && key.ends_with(')') {
+            let tokens = tokenize(&key[4..key.len() - 1]);
+            let mut pos = 0;
+            match self.eval(&tokens, &mut pos) {
+                Some(matched) => pos == tokens.len() && matched,
+                None => false,
+            }
+        } else {
+            key == self.triple
+        }
+    }
+
+    /// Evaluates the predicate starting at `tokens[*pos]`, or `None` if it's malformed
+    fn eval(&self, tokens: &[String], pos: &mut usize) -> Option<bool> {
+        let name = match tokens.get(*pos) {
+            Some(name) => name.clone(),
+            None => return None,
+        };
+        *pos += 1;
+        match tokens.get(*pos).map(|t| &**t) {
+            Some("(") => {
+                *pos += 1;
+                let mut results = vec![];
+                while tokens.get(*pos).map(|t| &**t) != Some(")") {
+                    match self.eval(tokens, pos) {
+                        Some(result) => results.push(result),
+                        None => return None,
+                    }
+                    if tokens.get(*pos).map(|t| &**t) == Some(",") {
+                        *pos += 1;
+                    }
+                }
+                *pos += 1;
+                match &*name {
+                    "all" => Some(results.iter().all(|&r| r)),
+                    "any" => Some(results.iter().any(|&r| r)),
+                    "not" if results.len() == 1 => Some(!results[0]),
+                    _ => None,
+                }
+            }
+            Some("=") => {
+                *pos += 1;
+                let value = match tokens.get(*pos) {
+                    Some(value) if value.starts_with('"') => value.trim_matches('"').to_owned(),
+                    _ => return None,
+                };
+                *pos += 1;
+                Some(self.cfgs.iter().any(|(n, v)| {
+                    *n == name && v.as_ref() == Some(&value)
+                }))
+            }
+            _ => Some(self.cfgs.iter().any(|(n, v)| *n == name && v.is_none())),
+        }
+    }
+}
+
+/// Splits a `cfg` expression into identifiers, quoted strings and punctuation
+fn tokenize(expr: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut chars = expr.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' | ')' | ',' | '=' => tokens.push(c.to_string()),
+            '"' => {
+                let mut s = String::from("\"");
+                for c in chars.by_ref() {
+                    s.push(c);
+                    if c == '"' {
+                        break;
+                    }
+                }
+                tokens.push(s);
+            }
+            c if c.is_whitespace() => (),
+            c => {
+                let mut ident = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if next.is_alphanumeric() || next == '_' {
+                        ident.push(next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(ident);
+            }
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linux() -> Target {
+        let cfgs = [
+            ("unix", None),
+            ("target_os", Some("linux")),
+            ("target_arch", Some("x86_64")),
+            ("target_feature", Some("sse2")),
+        ];
+        Target {
+            triple: "x86_64-unknown-linux-gnu".to_owned(),
+            cfgs: cfgs
+                .iter()
+                .map(|&(n, v)| (n.to_owned(), v.map(str::to_owned)))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn triples() {
+        assert!(linux().matches("x86_64-unknown-linux-gnu"));
+        assert!(!linux().matches("x86_64-pc-windows-msvc"));
+    }
+
+    #[test]
+    fn names_and_values() {
+        assert!(linux().matches("cfg(unix)"));
+        assert!(!linux().matches("cfg(windows)"));
+        assert!(linux().matches("cfg(target_os = \"linux\")"));
+        assert!(!linux().matches("cfg(target_os = \"macos\")"));
+        assert!(linux().matches("cfg(target_feature=\"sse2\")"));
+        // A name with values isn't set on its own
+        assert!(!linux().matches("cfg(target_os)"));
+    }
+
+    #[test]
+    fn predicates() {
+        assert!(linux().matches("cfg(not(windows))"));
+        assert!(linux().matches("cfg(all(unix, target_arch = \"x86_64\"))"));
+        assert!(!linux().matches("cfg(all(unix, target_arch = \"arm\"))"));
+        assert!(linux().matches("cfg(any(windows, target_os = \"linux\"))"));
+        assert!(!linux().matches("cfg(any())"));
+        assert!(linux().matches("cfg(all())"));
+    }
+
+    #[test]
+    fn malformed_expressions_dont_match() {
+        assert!(!linux().matches("cfg(unix"));
+        assert!(!linux().matches("cfg(not(unix, windows))"));
+        assert!(!linux().matches("cfg(target_os = linux)"));
+        assert!(!linux().matches("cfg(nope(unix))"));
+        assert!(!linux().matches("cfg(unix windows)"));
+    }
+}
@@ -1,15 +1,16 @@
 use std::env;
 use std::path::{Path, PathBuf};
 use std::fs;
+use std::io::Read;
 
 use error::{CliError, CliResult};
 
-pub fn find_file(file: &str, usr_override: bool) -> CliResult<PathBuf> {
+pub fn find_file(file: &Path, usr_override: bool) -> CliResult<PathBuf> {
     debugln!("util:find_file;file={:?};usr_override={:?}", file, usr_override);
     if usr_override {
-        return Ok(Path::new(file).to_path_buf());
+        return Ok(file.to_path_buf());
     }
-    let cwd = try!(env::current_dir());
+    let cwd = env::current_dir()?;
     let mut pwd = cwd.as_path();
 
     loop {
@@ -27,6 +28,128 @@ pub fn find_file(file: &str, usr_override: bool) -> CliResult<PathBuf> {
     }
 
     Err(CliError::Generic(format!("Could not find `{}` in `{}` or any parent directory",
-                                    file,
+                                    file.display(),
                                     pwd.display())))
 }
+
+/// Allows a user supplied path to name either the file itself or the directory containing it
+pub fn file_in_dir(path: &Path, file: &str) -> CliResult<PathBuf> {
+    debugln!("util:file_in_dir;path={:?};file={:?}", path, file);
+    let p = path;
+    if !p.is_dir() {
+        return Ok(p.to_path_buf());
+    }
+    let ret = p.join(file);
+    if !ret.is_file() {
+        return Err(CliError::Generic(format!("Could not find `{}` in `{}`", file, p.display())));
+    }
+    Ok(ret)
+}
+
+/// Whether `path` contains any glob wildcards
+pub fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// The manifests matched by the glob `pattern`
+///
+/// A matched directory stands for the `Cargo.toml` inside it, and matches which aren't a
+/// `Cargo.toml` are skipped.
+pub fn glob_manifests(pattern: &str) -> CliResult<Vec<PathBuf>> {
+    debugln!("util:glob_manifests;pattern={:?}", pattern);
+    let paths = ::glob::glob(pattern).map_err(|e| {
+        CliError::Generic(format!("Invalid manifest pattern `{}`: {}", pattern, e))
+    })?;
+    let mut manifests = vec![];
+    for path in paths {
+        let path = path.map_err(|e| CliError::Generic(e.to_string()))?;
+        let manifest = if path.is_dir() {
+            path.join("Cargo.toml")
+        } else {
+            path
+        };
+        if manifest.is_file() && manifest.file_name() == Some("Cargo.toml".as_ref()) {
+            manifests.push(manifest);
+        }
+    }
+    if manifests.is_empty() {
+        return Err(CliError::Generic(format!("No manifests match `{}`", pattern)));
+    }
+    Ok(manifests)
+}
+
+/// The `repository`, or else `homepage`, of a registry package, read from its manifest in the
+/// cargo home directory
+///
+/// Nothing is downloaded, so packages cargo hasn't unpacked yet have no link.
+pub fn package_link(name: &str, version: &str) -> Option<String> {
+    registry_package(name, version).and_then(|package| {
+        package
+            .get("repository")
+            .or_else(|| package.get("homepage"))
+            .and_then(|url| url.as_str())
+            .map(|url| url.to_owned())
+    })
+}
+
+/// The edition of a registry package, `None` when cargo hasn't unpacked it yet
+pub fn package_edition(name: &str, version: &str) -> Option<String> {
+    registry_package(name, version).map(|package| {
+        package
+            .get("edition")
+            .and_then(|edition| edition.as_str())
+            .unwrap_or("2015")
+            .to_owned()
+    })
+}
+
+/// The `[package]` table of a registry package unpacked in the cargo home directory
+fn registry_package(name: &str, version: &str) -> Option<::toml::Value> {
+    let cargo_home = match env::var_os("CARGO_HOME") {
+        Some(home) => PathBuf::from(home),
+        None => match env::home_dir() {
+            Some(home) => home.join(".cargo"),
+            None => return None,
+        },
+    };
+    let pattern = format!(
+        "{}/registry/src/*/{}-{}/Cargo.toml",
+        ::glob::Pattern::escape(&cargo_home.to_string_lossy()),
+        name,
+        version
+    );
+    let manifest = match ::glob::glob(&pattern).ok().and_then(|mut paths| paths.next()) {
+        Some(Ok(manifest)) => manifest,
+        _ => return None,
+    };
+    let mut contents = String::new();
+    if fs::File::open(&manifest)
+        .and_then(|mut f| f.read_to_string(&mut contents))
+        .is_err()
+    {
+        return None;
+    }
+    let manifest: ::toml::Value = match ::toml::from_str(&contents) {
+        Ok(manifest) => manifest,
+        Err(_) => return None,
+    };
+    manifest.get("package").cloned()
+}
+
+/// Whether `path` is a file which can be run
+#[cfg(unix)]
+pub fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path)
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+pub fn is_executable(path: &Path) -> bool { path.is_file() }
+
+#[cfg(feature = "color")]
+pub fn stdout_is_tty() -> bool { ::atty::is(::atty::Stream::Stdout) }
+
+#[cfg(not(feature = "color"))]
+pub fn stdout_is_tty() -> bool { false }
@@ -0,0 +1,232 @@
+//! Just enough version parsing to classify how big an update is and match simple requirements
+
+/// The numeric part of a version, ignoring any pre-release or build metadata
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl Version {
+    pub fn parse(s: &str) -> Option<Version> {
+        let numbers = s.split(['-', '+']).next().unwrap_or("");
+        let mut parts = numbers.split('.').map(|p| p.parse::<u64>());
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch)), None) => Some(Version {
+                major,
+                minor,
+                patch,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Whether `version` satisfies `requirement`, e.g. `^1.2`, `>=1.0, <2` or `1.2.3`
+///
+/// As in a manifest, a bare version is a caret requirement. Requirements which can't be parsed
+/// match nothing.
+pub fn matches(requirement: &str, version: &str) -> bool {
+    let version = match Version::parse(version) {
+        Some(v) => v,
+        None => return false,
+    };
+    requirement
+        .split(',')
+        .all(|comparator| comparator_matches(comparator.trim(), version))
+}
+
+fn comparator_matches(comparator: &str, v: Version) -> bool {
+    if comparator == "*" {
+        return true;
+    }
+    let op = [">=", "<=", ">", "<", "=", "^", "~"]
+        .iter()
+        .find(|op| comparator.starts_with(*op))
+        .copied();
+    let partial = comparator[op.map_or(0, |op| op.len())..].trim();
+    // Missing and `*` components are treated the same, e.g. `1.*` is `1`
+    let mut parts = partial.split('.').map(|p| p.parse::<u64>().ok());
+    let (major, minor, patch) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(Some(major)), minor, patch) => (major, minor.and_then(|m| m), patch.and_then(|p| p)),
+        _ => return false,
+    };
+    let lower = Version {
+        major,
+        minor: minor.unwrap_or(0),
+        patch: patch.unwrap_or(0),
+    };
+    let next = |major, minor, patch| Version {
+        major,
+        minor,
+        patch,
+    };
+    // The first version after those matching the partial version exactly
+    let exact_upper = match (minor, patch) {
+        (Some(minor), Some(patch)) => next(major, minor, patch + 1),
+        (Some(minor), None) => next(major, minor + 1, 0),
+        _ => next(major + 1, 0, 0),
+    };
+    match op.unwrap_or("^") {
+        "=" => lower <= v && v < exact_upper,
+        ">" => v >= exact_upper,
+        ">=" => v >= lower,
+        "<" => v < lower,
+        "<=" => v < exact_upper,
+        "~" => {
+            let upper = match minor {
+                Some(minor) => next(major, minor + 1, 0),
+                None => next(major + 1, 0, 0),
+            };
+            lower <= v && v < upper
+        }
+        _ => {
+            let upper = match (major, minor, patch) {
+                (0, Some(0), Some(patch)) => next(0, 0, patch + 1),
+                (0, Some(minor), _) => next(0, minor + 1, 0),
+                _ => next(major + 1, 0, 0),
+            };
+            lower <= v && v < upper
+        }
+    }
+}
+
+/// The caret requirement matching every version SemVer compatible with the lower bound of
+/// `requirement`, e.g. `~1.2.3` becomes `^1` and `=0.3.1` becomes `^0.3`
+pub fn compatible_requirement(requirement: &str) -> String {
+    let lower = requirement
+        .split(',')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .trim_start_matches(['^', '~', '=', '>', '<'])
+        .trim();
+    let mut parts = lower.split('.').map(|p| p.parse::<u64>().ok());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Some(0)), Some(Some(0)), Some(Some(patch))) => format!("^0.0.{}", patch),
+        (Some(Some(0)), Some(Some(minor)), _) => format!("^0.{}", minor),
+        (Some(Some(major)), _, _) => format!("^{}", major),
+        _ => "*".to_owned(),
+    }
+}
+
+/// How big an update is, from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Severity {
+    /// The severity of updating from `from` to `to`, or `None` if either can't be parsed
+    ///
+    /// As in cargo's SemVer rules, the leftmost non-zero number is the breaking one, so updating
+    /// `0.1.x` to `0.2.0`, or `0.0.1` to `0.0.2`, is major.
+    pub fn between(from: &str, to: &str) -> Option<Severity> {
+        let (from, to) = match (Version::parse(from), Version::parse(to)) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return None,
+        };
+        let pre_1_0_break = from.major == 0 && to.major == 0 &&
+            (from.minor != to.minor || (from.minor == 0 && from.patch != to.patch));
+        Some(if from.major != to.major || pre_1_0_break {
+            Severity::Major
+        } else if from.minor != to.minor {
+            Severity::Minor
+        } else {
+            Severity::Patch
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Severity::Patch => "patch",
+            Severity::Minor => "minor",
+            Severity::Major => "major",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bare_versions_are_caret_requirements() {
+        assert!(matches("1.2", "1.9.0"));
+        assert!(!matches("1.2", "2.0.0"));
+        assert!(!matches("1.2", "1.1.9"));
+        assert!(matches("0.3", "0.3.7"));
+        assert!(!matches("0.3", "0.4.0"));
+        assert!(matches("0.0.3", "0.0.3"));
+        assert!(!matches("0.0.3", "0.0.4"));
+    }
+
+    #[test]
+    fn operators() {
+        assert!(matches("~1.2.3", "1.2.9"));
+        assert!(!matches("~1.2.3", "1.3.0"));
+        assert!(matches("~1", "1.9.0"));
+        assert!(matches("=1.2.3", "1.2.3"));
+        assert!(!matches("=1.2.3", "1.2.4"));
+        assert!(matches("=1.2", "1.2.4"));
+        assert!(matches(">1.2", "1.3.0"));
+        assert!(!matches(">1.2", "1.2.9"));
+        assert!(matches("<=1.2", "1.2.9"));
+        assert!(!matches("<1.2", "1.2.0"));
+        assert!(matches("*", "7.0.0"));
+        assert!(matches("1.*", "1.4.0"));
+    }
+
+    #[test]
+    fn comma_separated_comparators_must_all_match() {
+        assert!(matches(">=1.0, <2", "1.5.0"));
+        assert!(!matches(">=1.0, <2", "2.0.0"));
+        assert!(!matches(">=1.0, <2", "0.9.0"));
+    }
+
+    #[test]
+    fn pre_release_and_build_metadata_are_ignored() {
+        assert!(matches("^1.2", "1.2.0-beta.1"));
+        assert!(matches("^1.2", "1.2.0+build.5"));
+    }
+
+    #[test]
+    fn unparsable_input_matches_nothing() {
+        assert!(!matches("^1.2", "latest"));
+        assert!(!matches("^one", "1.0.0"));
+    }
+
+    #[test]
+    fn compatible_requirement_keeps_the_breaking_number() {
+        assert_eq!(compatible_requirement("2.1.0"), "^2");
+        assert_eq!(compatible_requirement("~1.2.3"), "^1");
+        assert_eq!(compatible_requirement("=0.3.1"), "^0.3");
+        assert_eq!(compatible_requirement("0.0.4"), "^0.0.4");
+        assert_eq!(compatible_requirement(">=1.0, <3"), "^1");
+        assert_eq!(compatible_requirement("*"), "*");
+    }
+
+    #[test]
+    fn severity_from_1_0() {
+        assert_eq!(Severity::between("1.2.3", "1.2.4"), Some(Severity::Patch));
+        assert_eq!(Severity::between("1.2.3", "1.3.0"), Some(Severity::Minor));
+        assert_eq!(Severity::between("1.2.3", "2.0.0"), Some(Severity::Major));
+    }
+
+    #[test]
+    fn severity_before_1_0() {
+        assert_eq!(Severity::between("0.1.2", "0.1.3"), Some(Severity::Patch));
+        assert_eq!(Severity::between("0.1.2", "0.2.0"), Some(Severity::Major));
+        assert_eq!(Severity::between("0.0.1", "0.0.2"), Some(Severity::Major));
+        assert_eq!(Severity::between("0.9.0", "1.0.0"), Some(Severity::Major));
+    }
+
+    #[test]
+    fn severity_of_unparsable_versions() {
+        assert_eq!(Severity::between("1.0", "1.1.0"), None);
+        assert_eq!(Severity::between("1.0.0", "RM"), None);
+    }
+}